@@ -39,6 +39,18 @@ fn insert_app_data() -> Result<()> {
         }
         writeln!(f, r#"    .quad app_{}_end"#, apps.len() - 1)?;
 
+        //应用名字表，每个名字以 \0 结尾，exec 通过名字查找应用
+        writeln!(
+            f,
+            r#"
+        .global _app_names
+    _app_names:"#
+        )?;
+        for app in apps.iter() {
+            writeln!(f, r#"    .string "{}""#, app)?;
+        }
+
+        //直接嵌入应用的 ELF 文件，内核通过 MemorySet::from_elf 解析程序头并建立地址空间
         for (idx, app) in apps.iter().enumerate() {
             println!("app_{}: {}", idx, app);
             writeln!(
@@ -47,8 +59,9 @@ fn insert_app_data() -> Result<()> {
         .section .data
         .global app_{0}_start
         .global app_{0}_end
+        .align 3
     app_{0}_start:
-        .incbin "{2}{1}.bin"
+        .incbin "{2}{1}"
     app_{0}_end:"#,
                 idx, app, TARGET_PATH
             )?;
@@ -1,14 +1,35 @@
-pub const MAX_APP_NUM: usize = 16;
-pub const APP_BASE_ADDRESS: usize = 0x80400000;
-pub const APP_SIZE_LIMIT: usize = 0x20000;
 pub const USER_STACK_SIZE: usize = 4096 * 2;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
 pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const MEMORY_END: usize = 0x80800000;
+//stride 调度算法中的 BigStride ，每个任务的步长为 BIG_STRIDE / priority
+pub const BIG_STRIDE: usize = 0x10000;
+//任务的默认优先级
+pub const DEFAULT_PRIORITY: usize = 16;
+//任务默认的时间片长度（ms）
+pub const DEFAULT_TIME_SLICE: usize = 10;
+//sys_set_time_slice 允许设置的最大时间片长度（ms）
+pub const MAX_TIME_SLICE: usize = 10_000;
+//有任务在等待控制台输入时，检查输入的时钟中断间隔（ms）
+pub const TTY_POLL_INTERVAL: usize = 10;
+//统计系统调用次数时支持的最大系统调用号（不含），需要覆盖线程和同步原语使用的 1000 以上的系统调用号
+pub const MAX_SYSCALL_NUM: usize = 1040;
+
+//各个线程的用户栈从这里开始往上依次排列，直到 Sv39 地址空间低半部分的顶端
+//堆和 mmap 只能使用它下面的地址，高半部分留给跳板页面和 Trap 上下文
+pub const USER_STACK_BASE: usize = 0x3f_0000_0000;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
 
+//内核栈在内核地址空间中的位置 [bottom, top)
+//从跳板页面往下依次排列，每个内核栈下面留一个不映射的保护页，栈溢出时触发缺页而不是破坏相邻的内核栈
+pub fn kernel_stack_position(pid: usize) -> (usize, usize) {
+    let top = TRAMPOLINE - pid * (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let bottom = top - KERNEL_STACK_SIZE;
+    (bottom, top)
+}
+
 pub use crate::board::CLOCK_FREQ;
\ No newline at end of file
@@ -1,50 +1,5 @@
-use core::arch::asm;
-use crate::trap::TrapContext;
-use crate::config::*;
-
-//声明静态数组，数组包含MAX_APP_NUM个KernelStack元素
-static KERNEL_STACK: [KernelStack; MAX_APP_NUM] = [KernelStack { data: [0; KERNEL_STACK_SIZE] }; MAX_APP_NUM];
-
-static USER_STACK: [UserStack; MAX_APP_NUM] = [UserStack { data: [0; USER_STACK_SIZE] }; MAX_APP_NUM];
-
-#[repr(align(4096))] //#[repr] 属性有一个新参数 align，用于设置结构体的对齐方式
-#[derive(Clone, Copy)] //结构体或枚举正在请求Rust编译器自动为它实现 Clone 和 Copy 这两个trait。
-struct KernelStack {
-    data: [u8; KERNEL_STACK_SIZE] //8192个u8
-}
-#[repr(align(4096))]
-#[derive(Clone, Copy)]
-struct UserStack {
-    data: [u8; USER_STACK_SIZE]
-}
-
-impl KernelStack {
-    fn get_sp(&self) -> usize {
-        self.data.as_ptr() as usize + KERNEL_STACK_SIZE
-    }
-
-    fn push_context(&self, trap_cx: TrapContext) -> usize {
-        //从 get_sp 获得的地址中减去 TrapContext 的大小
-        let trap_cx_prt = (self.get_sp() - core::mem::size_of::<TrapContext>()) as *mut TrapContext;
-        //储存
-        unsafe {
-            *trap_cx_prt = trap_cx;
-        }
-        //返回指针，表示trap_cx在栈中的地址
-        trap_cx_prt as usize
-    }
-}
-
-impl UserStack {
-    fn get_sp(&self) -> usize {
-        self.data.as_ptr() as usize + USER_STACK_SIZE
-    }
-}
-
-//得到对应app的起始地址
-fn get_base_i(app_id: usize) -> usize {
-    APP_BASE_ADDRESS + app_id * APP_SIZE_LIMIT
-}
+use alloc::vec::Vec;
+use lazy_static::*;
 
 //得到app数量
 pub fn get_num_app() -> usize {
@@ -54,6 +9,7 @@ pub fn get_num_app() -> usize {
     unsafe { (_num_app as usize as *const usize).read_volatile() }
 }
 //获取|appi|
+//应用不再被拷贝到固定的物理地址，而是直接交给 MemorySet::from_elf 解析
 pub fn get_app_data(app_id: usize) -> &'static [u8] {
     extern "C" { fn _num_app(); } //link_app.S内app数据信息的位置
 
@@ -68,53 +24,48 @@ pub fn get_app_data(app_id: usize) -> &'static [u8] {
 
     unsafe {
         core::slice::from_raw_parts(
-            app_start[app_id] as *const u8, 
+            app_start[app_id] as *const u8,
             app_start[app_id + 1] - app_start[app_id])
     }
 }
 
-//加载
-pub fn load_app() {
-    extern "C" { fn _num_app(); } //link_app.S内app数据信息的位置
-
-    let num_app_ptr = _num_app as usize as *const usize; //先转换为usize类型，在转化为指向usize的常量指针
-    let num_app = get_num_app(); //从指针位置读取值 也就是app数量
-    //|num_app|app0|app1|app2|app3|app4|app4end|
-    //获取app0 ~ app4end
-    let app_start = unsafe {
-        core::slice::from_raw_parts(num_app_ptr.add(1), num_app + 1)
-    };
-
-    for i in 0..num_app {
-        let base_i = get_base_i(i);
-        //清零base_i ~ base_i + 0x20000
-        (base_i..base_i + APP_SIZE_LIMIT).for_each(|addr| unsafe {
-            (addr as *mut u8).write_volatile(0)
-        });
-        //在app_start中获取 app i 的待加载 app 所占的内存空间 
-        //|app0|app1|app2|app3|app4|app4end|
-        //如果i = 0 则获取到 |app0|
-        let src = unsafe {
-            core::slice::from_raw_parts(app_start[i] as *const u8, app_start[i + 1] - app_start[i])
-        };
-        //获取 从appi的内存起始地址开始 所占的内存空间
-        let dst = unsafe {
-            core::slice::from_raw_parts_mut(base_i as *mut u8, src.len())
-        };
-        //拷贝到dst执行代码区域
-        dst.copy_from_slice(src);
-        //刷新指令缓存
+lazy_static! {
+    //所有应用的名字，下标就是应用编号
+    //link_app.S 中的 _app_names 按顺序存放了每个应用以 \0 结尾的名字
+    static ref APP_NAMES: Vec<&'static str> = {
+        let num_app = get_num_app();
+        extern "C" { fn _app_names(); }
+        let mut start = _app_names as usize as *const u8;
+        let mut v = Vec::new();
         unsafe {
-            asm!("fence.i");
+            for _ in 0..num_app {
+                let mut end = start;
+                while end.read_volatile() != b'\0' {
+                    end = end.add(1);
+                }
+                let slice = core::slice::from_raw_parts(start, end as usize - start as usize);
+                let str = core::str::from_utf8(slice).unwrap();
+                v.push(str);
+                start = end.add(1);
+            }
         }
-    }
+        v
+    };
 }
 
-//返回cx：trap上下文的栈顶指针，通过__restore赋值给a0，进而恢复上下文，跳转到appi的入口点entry
-pub fn init_app_cx(app_id: usize) -> usize {
-    //println!("[kernel] spec:{:X}", get_base_i(app_id));
-    KERNEL_STACK[app_id].push_context(TrapContext::app_init_context(
-        get_base_i(app_id), 
-        USER_STACK[app_id].get_sp()
-    ))
-}
\ No newline at end of file
+//通过应用名获取应用的 ELF 数据
+pub fn get_app_data_by_name(name: &str) -> Option<&'static [u8]> {
+    let num_app = get_num_app();
+    (0..num_app)
+        .find(|&i| APP_NAMES[i] == name)
+        .map(get_app_data)
+}
+
+//打印内核中所有可以通过名字加载的应用
+pub fn list_apps() {
+    println!("/**** APPS ****");
+    for app in APP_NAMES.iter() {
+        println!("{}", app);
+    }
+    println!("**************/");
+}
@@ -26,6 +26,7 @@ mod loader;
 mod task;
 mod timer;
 mod mm;
+mod tty;
 
 global_asm!(include_str!("entry.asm"));
 global_asm!(include_str!("link_app.S"));
@@ -74,13 +75,15 @@ pub fn rust_main() -> ! {
     //sbi::shutdown(false);
     mm::init();
     mm::heap_allocator::heap_test();
+    mm::remap_test();
     trap::init();
 
     //batch::init();
-    loader::load_app();
     trap::enable_timer_interrupt();
     timer::set_next_trigger();
-    task::run_first_task();
+    loader::list_apps();
+    task::add_initproc();
+    task::run_tasks();
     //batch::run_next_app();
     panic!("Unreachable in rust_main!");
 }
@@ -135,6 +135,13 @@ impl PhysAddr {
     pub fn aligned(&self) -> bool { self.page_offset() == 0 }
 }
 
+impl PhysAddr {
+    //获取物理地址处类型为 T 的数据的可变引用
+    pub fn get_mut<T>(&self) -> &'static mut T {
+        unsafe { (self.0 as *mut T).as_mut().unwrap() }
+    }
+}
+
 impl PhysPageNum {
     //获取一个指向物理页框内存的可变字节数组
     pub fn get_bytes_array(&self) -> &'static mut [u8] {
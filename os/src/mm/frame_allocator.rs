@@ -76,6 +76,7 @@ pub fn init_frame_allocator() {
 }
 
 //为什么要封装？ 封装后可以为其实现 Drop Trait ，就不必手动回收物理页帧了。在编译期就解决了很多潜在的问题。
+//一个页帧被多个地址空间共享（写时复制）时用 Arc 包装，引用计数归零时才回收
 pub struct FrameTracker {
     pub ppn: PhysPageNum,
 }
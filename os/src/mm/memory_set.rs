@@ -1,9 +1,14 @@
-use crate::config::{MEMORY_END, PAGE_SIZE, USER_STACK_SIZE, TRAMPOLINE, TRAP_CONTEXT};
+use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE};
+use crate::sync::UPSafeCell;
 
-use super::{frame_alloc, PTEFlags, FrameTracker, PageTable, PhysPageNum, VPNRange, VirtAddr, VirtPageNum, StepByOne};
+use super::{frame_alloc, PTEFlags, FrameTracker, PageTable, PageTableEntry, PhysAddr, PhysPageNum, VPNRange, VirtAddr, VirtPageNum, StepByOne};
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use bitflags::bitflags;
+use core::arch::asm;
+use lazy_static::*;
+use riscv::register::satp;
 
 extern "C" {
     fn stext();
@@ -18,10 +23,18 @@ extern "C" {
     fn strampoline();
 }
 
+lazy_static! {
+    //内核地址空间，在第一次被使用到的时候才会实际初始化
+    pub static ref KERNEL_SPACE: UPSafeCell<MemorySet> = unsafe {
+        UPSafeCell::new(MemorySet::new_kernel())
+    };
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum MapType {
     Identical, //恒等映射
     Framed, //每个虚拟页面都有一个新分配的物理页帧与之对应，虚地址与物理地址的映射关系是相对随机的。
+    Lazy, //和 Framed 一样，但页帧在虚拟页面第一次被访问、触发缺页异常时才分配
 }
 
 bitflags! {
@@ -35,9 +48,10 @@ bitflags! {
 
 pub struct MapArea {
     vpn_range: VPNRange, //描述一段虚拟页号的连续区间，表示该逻辑段在地址区间中的位置和长度。
-    data_frames: BTreeMap<VirtPageNum, FrameTracker>, //保存了该逻辑段内的每个虚拟页面和它被映射到的物理页帧 FrameTracker 的一个键值对容器 BTreeMap 中
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>, //保存了该逻辑段内的每个虚拟页面和它被映射到的物理页帧 FrameTracker 的一个键值对容器 BTreeMap 中，fork 之后的页帧可能被多个地址空间共享
     map_type: MapType, //逻辑段内的所有虚拟页面映射到物理页帧的方式
     map_perm: MapPermission, //控制该逻辑段的访问方式
+    lazy_data: Option<&'static [u8]>, //Lazy 逻辑段页面的初始内容（ELF 中的数据），从逻辑段开头对齐，超出的部分为零
 }
 
 impl MapArea {
@@ -49,6 +63,17 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            lazy_data: None,
+        }
+    }
+    //复制一个逻辑段的元数据（虚拟页号区间、映射方式和权限），不包括实际的物理页帧
+    pub fn from_another(another: &MapArea) -> Self {
+        Self {
+            vpn_range: VPNRange::new(another.vpn_range.get_start(), another.vpn_range.get_end()),
+            data_frames: BTreeMap::new(),
+            map_type: another.map_type,
+            map_perm: another.map_perm,
+            lazy_data: another.lazy_data,
         }
     }
     //将单个虚拟页号 vpn 映射到一个物理页号 ppn，并将映射关系添加到页表中。
@@ -58,10 +83,18 @@ impl MapArea {
             MapType::Identical => {
                 ppn = PhysPageNum(vpn.0);
             }
-            MapType::Framed => {
+            MapType::Framed | MapType::Lazy => {
                 let frame = frame_alloc().unwrap();
                 ppn = frame.ppn;
-                self.data_frames.insert(vpn, frame);
+                //按需加载的页面从 ELF 数据中对应的位置复制初始内容
+                if let Some(data) = self.lazy_data {
+                    let offset = (vpn.0 - self.vpn_range.get_start().0) * PAGE_SIZE;
+                    if offset < data.len() {
+                        let src = &data[offset..data.len().min(offset + PAGE_SIZE)];
+                        ppn.get_bytes_array()[..src.len()].copy_from_slice(src);
+                    }
+                }
+                self.data_frames.insert(vpn, Arc::new(frame));
             }
         }
         //将 MapPermission 中的权限标志位转换为 PTEFlags 类型，这样这些权限就可以正确应用于页表项。
@@ -74,12 +107,22 @@ impl MapArea {
             MapType::Framed => {
                 self.data_frames.remove(&vpn);
             }
+            MapType::Lazy => {
+                //还没有被访问过的页面没有映射
+                if self.data_frames.remove(&vpn).is_none() {
+                    return;
+                }
+            }
             _ => {}
         }
         page_table.unmap(vpn);
     }
 
     pub fn map(&mut self, page_table: &mut PageTable) {
+        //Lazy 逻辑段只保留虚拟页号区间，等到缺页时再由 map_one 逐页映射
+        if self.map_type == MapType::Lazy {
+            return;
+        }
         for vpn in self.vpn_range {
             self.map_one(page_table, vpn);
         }
@@ -90,6 +133,79 @@ impl MapArea {
             self.unmap_one(page_table, vpn);
         }
     }
+    //逻辑段是否包含虚拟页号 vpn
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.get_start() <= vpn && vpn < self.vpn_range.get_end()
+    }
+    //把逻辑段从虚拟页号 at 处分成两段，自己保留 [start, at) ，返回 [at, end)
+    fn split_off(&mut self, at: VirtPageNum) -> MapArea {
+        let start = self.vpn_range.get_start();
+        let offset = (at.0 - start.0) * PAGE_SIZE;
+        let tail = MapArea {
+            vpn_range: VPNRange::new(at, self.vpn_range.get_end()),
+            data_frames: self.data_frames.split_off(&at),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            lazy_data: self.lazy_data.map(|data| &data[offset.min(data.len())..]),
+        };
+        self.vpn_range = VPNRange::new(start, at);
+        tail
+    }
+    //逻辑段缩短到 new_end 结束，回收后面的页面；new_end 不小于当前结束位置时什么都不做
+    pub fn shrink_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        let (start, end) = (self.vpn_range.get_start(), self.vpn_range.get_end());
+        if new_end >= end {
+            return;
+        }
+        for vpn in VPNRange::new(new_end, end) {
+            self.unmap_one(page_table, vpn);
+        }
+        self.vpn_range = VPNRange::new(start, new_end);
+    }
+    //逻辑段延长到 new_end 结束，Lazy 逻辑段新增的页面同样在第一次访问时才分配；new_end 不大于当前结束位置时什么都不做
+    pub fn append_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        let (start, end) = (self.vpn_range.get_start(), self.vpn_range.get_end());
+        if new_end <= end {
+            return;
+        }
+        self.vpn_range = VPNRange::new(start, new_end);
+        if self.map_type != MapType::Lazy {
+            for vpn in VPNRange::new(end, new_end) {
+                self.map_one(page_table, vpn);
+            }
+        }
+    }
+    //修改逻辑段的访问权限，并重写其中已经映射的页面的页表项
+    //还被其他地址空间共享的页帧保持只读，第一次写入时再复制
+    fn set_permission(&mut self, page_table: &mut PageTable, permission: MapPermission) {
+        self.map_perm = permission;
+        for (&vpn, frame) in self.data_frames.iter() {
+            let mut flags = PTEFlags::from_bits(permission.bits).unwrap();
+            if Arc::strong_count(frame) > 1 {
+                flags.remove(PTEFlags::W);
+            }
+            page_table.set_flags(vpn, flags);
+        }
+    }
+    //逻辑段和虚拟页号区间 [start, end) 是否有交集
+    fn overlaps(&self, start: VirtPageNum, end: VirtPageNum) -> bool {
+        self.vpn_range.get_start() < end && start < self.vpn_range.get_end()
+    }
+    //写时复制：页帧还被其他地址空间共享时复制一份给自己，然后按逻辑段的权限重新映射 vpn
+    //页帧只剩自己在用时不需要复制，直接恢复写权限
+    fn copy_on_write(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        let frame = self.data_frames.get(&vpn).unwrap();
+        if Arc::strong_count(frame) > 1 {
+            let new_frame = frame_alloc().unwrap();
+            new_frame
+                .ppn
+                .get_bytes_array()
+                .copy_from_slice(frame.ppn.get_bytes_array());
+            self.data_frames.insert(vpn, Arc::new(new_frame));
+        }
+        let ppn = self.data_frames.get(&vpn).unwrap().ppn;
+        page_table.remap(vpn, ppn, PTEFlags::from_bits(self.map_perm.bits).unwrap());
+    }
     //将给定的数据（切片 data）逐页拷贝到当前逻辑段（MapArea）对应的物理页帧中。
     //切片 data 中的数据大小不超过当前逻辑段的总大小，且切片中的数据会被对齐到逻辑段的开头，然后逐页拷贝到实际的物理页帧。
     pub fn copy_data(&mut self, page_table: &PageTable, data: &[u8]) {
@@ -125,6 +241,23 @@ impl MemorySet {
             areas: Vec::new(),
         }
     }
+    //获取地址空间的 token ，即 satp 的值
+    pub fn token(&self) -> usize {
+        self.page_table.token()
+    }
+    //将 token 写入 satp 切换到该地址空间，MODE 为 8 即开启 Sv39 分页
+    //切换之后原来快表中的键值对都已经失效，需要用 sfence.vma 清空快表
+    pub fn activate(&self) {
+        let satp = self.page_table.token();
+        unsafe {
+            satp::write(satp);
+            asm!("sfence.vma");
+        }
+    }
+    //在地址空间的多级页表中查找虚拟页号对应的页表项
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.page_table.translate(vpn)
+    }
     //在当前地址空间插入一个新的逻辑段
     fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
         map_area.map(&mut self.page_table);
@@ -139,6 +272,201 @@ impl MemorySet {
         self.push(MapArea::new(start_va, end_va, MapType::Framed, permission), None);
 
     }
+    //插入一个 Lazy 方式映射的逻辑段，页帧在第一次访问时才分配
+    pub fn insert_lazy_area(&mut self, start_va: VirtAddr, end_va: VirtAddr, permission: MapPermission) {
+        self.push(MapArea::new(start_va, end_va, MapType::Lazy, permission), None);
+    }
+    //在 [start_va, end_va) 映射一段按需分配的匿名内存，和已有的逻辑段重叠时返回 false
+    pub fn mmap(&mut self, start_va: VirtAddr, end_va: VirtAddr, permission: MapPermission) -> bool {
+        let (start_vpn, end_vpn) = (start_va.floor(), end_va.ceil());
+        if self.areas.iter().any(|area| area.overlaps(start_vpn, end_vpn)) {
+            return false;
+        }
+        self.insert_lazy_area(start_va, end_va, permission);
+        true
+    }
+    //检查 [start_vpn, end_vpn) 中的每个页面都属于应用可以访问的逻辑段，然后在区间的两端把逻辑段拆开，
+    //使得和区间有交集的逻辑段都完全位于区间之内
+    //区间中有页面没有被映射，或者属于应用不能访问的逻辑段（例如 Trap 上下文）时什么都不做并返回 false
+    fn split_at_range(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        let mut covered = 0;
+        for area in self.areas.iter().filter(|area| area.overlaps(start_vpn, end_vpn)) {
+            if area.map_type == MapType::Identical || !area.map_perm.contains(MapPermission::U) {
+                return false;
+            }
+            covered += area.vpn_range.get_end().min(end_vpn).0 - area.vpn_range.get_start().max(start_vpn).0;
+        }
+        if covered != end_vpn.0 - start_vpn.0 {
+            return false;
+        }
+        //拆出来的逻辑段放在最后，不需要再检查
+        for idx in 0..self.areas.len() {
+            if !self.areas[idx].overlaps(start_vpn, end_vpn) {
+                continue;
+            }
+            if self.areas[idx].vpn_range.get_end() > end_vpn {
+                let tail = self.areas[idx].split_off(end_vpn);
+                self.areas.push(tail);
+            }
+            if self.areas[idx].vpn_range.get_start() < start_vpn {
+                let middle = self.areas[idx].split_off(start_vpn);
+                self.areas.push(middle);
+            }
+        }
+        true
+    }
+    //解除 [start_va, end_va) 的映射，只解除逻辑段的一部分时把它拆开，不能解除时返回 false
+    pub fn munmap(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> bool {
+        let (start_vpn, end_vpn) = (start_va.floor(), end_va.ceil());
+        if !self.split_at_range(start_vpn, end_vpn) {
+            return false;
+        }
+        let page_table = &mut self.page_table;
+        self.areas.retain_mut(|area| {
+            if area.overlaps(start_vpn, end_vpn) {
+                area.unmap(page_table);
+                false
+            } else {
+                true
+            }
+        });
+        true
+    }
+    //把 [start_va, end_va) 的访问权限改为 permission ，只修改逻辑段的一部分时把它拆开，不能修改时返回 false
+    pub fn mprotect(&mut self, start_va: VirtAddr, end_va: VirtAddr, permission: MapPermission) -> bool {
+        let (start_vpn, end_vpn) = (start_va.floor(), end_va.ceil());
+        if !self.split_at_range(start_vpn, end_vpn) {
+            return false;
+        }
+        for area in self
+            .areas
+            .iter_mut()
+            .filter(|area| area.overlaps(start_vpn, end_vpn))
+        {
+            area.set_permission(&mut self.page_table, permission);
+        }
+        //权限被收回时，快表中旧的页表项必须立即失效，不依赖回到用户态切换 satp 时的刷新
+        unsafe {
+            asm!("sfence.vma");
+        }
+        true
+    }
+    //缩短起始地址为 start 的逻辑段，使它结束于 new_end ，找不到逻辑段时返回 false
+    pub fn shrink_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range.get_start() == start.floor())
+        {
+            Some(area) => {
+                area.shrink_to(&mut self.page_table, new_end.ceil());
+                true
+            }
+            None => false,
+        }
+    }
+    //延长起始地址为 start 的逻辑段，使它结束于 new_end ，找不到逻辑段或者延长的部分和其他逻辑段重叠时返回 false
+    pub fn append_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        let idx = match self
+            .areas
+            .iter()
+            .position(|area| area.vpn_range.get_start() == start.floor())
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let (old_end, new_end) = (self.areas[idx].vpn_range.get_end(), new_end.ceil());
+        if self
+            .areas
+            .iter()
+            .enumerate()
+            .any(|(i, area)| i != idx && area.overlaps(old_end, new_end))
+        {
+            return false;
+        }
+        self.areas[idx].append_to(&mut self.page_table, new_end);
+        true
+    }
+    //删除起始虚拟页号为 start_vpn 的逻辑段，回收它占用的物理页帧
+    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
+        if let Some((idx, area)) = self
+            .areas
+            .iter_mut()
+            .enumerate()
+            .find(|(_, area)| area.vpn_range.get_start() == start_vpn)
+        {
+            area.unmap(&mut self.page_table);
+            self.areas.remove(idx);
+        }
+    }
+    //处理应用在 vpn 处发生的缺页异常，write 表示是否是写访问，返回是否处理了
+    //页面还没有映射时，属于 Lazy 逻辑段就现在分配页帧；已经映射时只可能是对写时复制页面的写入
+    //修改的是应用地址空间的页表，回到用户态切换 satp 时会执行 sfence.vma ，不需要在这里刷新快表
+    pub fn handle_page_fault(&mut self, vpn: VirtPageNum, write: bool) -> bool {
+        match self.page_table.translate(vpn) {
+            Some(pte) if pte.is_valid() => write && !pte.writable() && self.handle_cow(vpn),
+            _ => match self.areas.iter_mut().find(|area| area.contains(vpn)) {
+                Some(area) if area.map_type == MapType::Lazy => {
+                    area.map_one(&mut self.page_table, vpn);
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+    //vpn 是写时复制的页面（逻辑段可写但页表项没有写权限）时为它准备一个可写的页帧，返回是否处理了
+    fn handle_cow(&mut self, vpn: VirtPageNum) -> bool {
+        match self.areas.iter_mut().find(|area| area.contains(vpn)) {
+            Some(area)
+                if area.map_type != MapType::Identical
+                    && area.map_perm.contains(MapPermission::W) =>
+            {
+                area.copy_on_write(&mut self.page_table, vpn);
+                true
+            }
+            _ => false,
+        }
+    }
+    //内核通过物理地址直接访问应用地址空间中的 [start, start + len) 之前调用
+    //这种访问不会触发缺页异常，需要提前映射其中还没有被访问过的页面；
+    //write 为 true 时还要复制其中写时复制的页面，否则会写到与其他进程共享的页帧中
+    pub fn prepare_access(&mut self, start: usize, len: usize, write: bool) {
+        let start_vpn = VirtAddr::from(start).floor();
+        let end_vpn = VirtAddr::from(start.saturating_add(len)).ceil();
+        for vpn in VPNRange::new(start_vpn, end_vpn) {
+            self.handle_page_fault(vpn, false);
+            if write {
+                self.handle_page_fault(vpn, true);
+            }
+        }
+    }
+    //内核读取应用地址空间中从 start 开始、以 \0 结尾的字符串之前调用，逐页映射直到找到 \0
+    pub fn prepare_str(&mut self, start: usize) {
+        let mut va = VirtAddr::from(start);
+        loop {
+            let mut vpn = va.floor();
+            self.handle_page_fault(vpn, false);
+            let ppn = match self.page_table.translate(vpn) {
+                Some(pte) if pte.is_valid() => pte.ppn(),
+                _ => return,
+            };
+            if ppn.get_bytes_array()[va.page_offset()..].contains(&0) {
+                return;
+            }
+            vpn.step();
+            va = vpn.into();
+        }
+    }
+    //映射跳板页面：将 TRAMPOLINE 映射到 .text.trampoline 所在的物理页帧
+    //跳板页面不属于任何逻辑段，也不会被回收，所以直接在页表中插入键值对
+    //不设置 U 标志位，只有在 S 特权级才能访问
+    fn map_trampoline(&mut self) {
+        self.page_table.map(
+            VirtAddr::from(TRAMPOLINE).into(),
+            PhysAddr::from(strampoline as usize).into(),
+            PTEFlags::R | PTEFlags::X,
+        );
+    }
     //生成内核的地址空间
     pub fn new_kernel() -> Self {
         let mut memory_set = Self::new_bare();
@@ -187,8 +515,48 @@ impl MemorySet {
         ), None);
         memory_set
     }
+    //复制一个用户地址空间，fork 时用来生成子进程的地址空间
+    //用户可以访问的页面不复制，而是让父子进程共享同一个页帧（写时复制），
+    //其中可写的页面在双方的页表中都去掉写权限，等到第一次写入触发缺页时再由 handle_cow 复制
+    pub fn from_existed_user(user_space: &mut MemorySet) -> MemorySet {
+        let mut memory_set = Self::new_bare();
+        // map trampoline
+        memory_set.map_trampoline();
+        for area in user_space.areas.iter() {
+            let mut new_area = MapArea::from_another(area);
+            //还没有被访问过的 Lazy 页面不需要处理，在子进程中同样会按需分配
+            if area.map_type != MapType::Identical && area.map_perm.contains(MapPermission::U) {
+                let mut flags = PTEFlags::from_bits(area.map_perm.bits).unwrap();
+                flags.remove(PTEFlags::W);
+                for (&vpn, frame) in area.data_frames.iter() {
+                    user_space.page_table.set_flags(vpn, flags);
+                    memory_set.page_table.map(vpn, frame.ppn, flags);
+                    new_area.data_frames.insert(vpn, Arc::clone(frame));
+                }
+                memory_set.areas.push(new_area);
+                continue;
+            }
+            //Trap 上下文这样只有内核访问的页面是通过物理地址直接读写的，不会触发缺页，必须立即复制
+            memory_set.push(new_area, None);
+            // copy data from another space
+            //逐页复制数据，新旧两个地址空间中的同一个虚拟页号对应不同的物理页帧
+            for vpn in area.vpn_range {
+                let src_ppn = user_space.translate(vpn).unwrap().ppn();
+                let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
+                dst_ppn
+                    .get_bytes_array()
+                    .copy_from_slice(src_ppn.get_bytes_array());
+            }
+        }
+        memory_set
+    }
+    //回收地址空间中所有逻辑段的物理页帧，多级页表所占的页帧在 MemorySet 被回收时才回收
+    pub fn recycle_data_pages(&mut self) {
+        self.areas.clear();
+    }
     //分析应用的 ELF 文件格式的内容，解析出各数据段并生成对应的地址空间。
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
+    //各个段都以 Lazy 方式映射，页面在第一次被访问时才从 elf_data 中复制，所以 elf_data 需要一直有效
+    pub fn from_elf(elf_data: &'static [u8]) -> (Self, usize, usize) {
         //包含elf、trampoline、TrapContext和user stack中的部分，
         //同样返回user_sp和入口点
         let mut memory_set = Self::new_bare();
@@ -243,37 +611,41 @@ impl MemorySet {
                     map_perm |= MapPermission::X; 
                 }
                 //创建逻辑段 map_area 
-                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+                let mut map_area = MapArea::new(start_va, end_va, MapType::Lazy, map_perm);
                 max_end_vpn = map_area.vpn_range.get_end();
                 // push 到应用地址空间
-                //在 push 的时候我们需要完成数据拷贝，当前 program header 数据被存放的位置可以通过 ph.offset() 和 ph.file_size() 来找到。
+                //数据拷贝推迟到缺页时进行，当前 program header 数据被存放的位置可以通过 ph.offset() 和 ph.file_size() 来找到。
                 //注意当存在一部分零初始化的时候， ph.file_size() 将会小于 ph.mem_size() ，因为这些零出于缩减可执行文件大小的原因不应该实际出现在 ELF 数据中。
-                memory_set.push(map_area, Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]));
+                map_area.lazy_data = Some(&elf_data[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]);
+                memory_set.push(map_area, None);
             }
         }
-        // 映射用户栈 max_end_vpn 记录目前涉及到的最大的虚拟页号
+        // max_end_vpn 记录目前涉及到的最大的虚拟页号
         let max_end_va: VirtAddr = max_end_vpn.into();
-        //用户栈底虚拟地址
-        let mut user_stack_bottom: usize = max_end_va.into();
-        // 在max_end_vpn上面再放置一个保护页面和用户栈即可。
-        //保护页面guard page
-        user_stack_bottom += PAGE_SIZE;
-        let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
-        memory_set.push(MapArea::new(
-            user_stack_bottom.into(),
-            user_stack_top.into(),
-            MapType::Framed,
+        //在 max_end_vpn 上面先空出一个保护页面，之后是堆，一开始长度为 0 ，通过 sbrk 向上增长
+        //用户栈和 Trap 上下文由线程创建时分配在 USER_STACK_BASE 之上
+        let mut heap_bottom: usize = max_end_va.into();
+        heap_bottom += PAGE_SIZE;
+        memory_set.insert_lazy_area(
+            heap_bottom.into(),
+            heap_bottom.into(),
             MapPermission::R | MapPermission::W | MapPermission::U,
-        ), None);
-        // map TrapContext
-        //在应用地址空间中映射次高页面来存放 Trap 上下文。
-        memory_set.push(MapArea::new(
-            TRAP_CONTEXT.into(),
-            TRAMPOLINE.into(),
-            MapType::Framed,
-            MapPermission::R | MapPermission::W,
-        ), None);
-        //不仅返回应用地址空间 memory_set ，也同时返回用户栈虚拟地址 user_stack_top 以及从解析 ELF 得到的该应用入口点地址，它们将被我们用来创建应用的任务控制块。
-        (memory_set, user_stack_top, elf.header.pt2.entry_point() as usize)
+        );
+        //不仅返回应用地址空间 memory_set ，也同时返回堆底的地址以及从解析 ELF 得到的该应用入口点地址
+        (memory_set, heap_bottom, elf.header.pt2.entry_point() as usize)
     }
-}
\ No newline at end of file
+}
+
+//检查内核地址空间的多级页表是否被正确设置：
+//分别取 .text/.rodata/.data 中间的一个地址，确认 .text 和 .rodata 不可写、 .data 不可执行
+#[allow(unused)]
+pub fn remap_test() {
+    let kernel_space = KERNEL_SPACE.exclusive_access();
+    let mid_text: VirtAddr = ((stext as usize + etext as usize) / 2).into();
+    let mid_rodata: VirtAddr = ((srodata as usize + erodata as usize) / 2).into();
+    let mid_data: VirtAddr = ((sdata as usize + edata as usize) / 2).into();
+    assert!(!kernel_space.page_table.translate(mid_text.floor()).unwrap().writable());
+    assert!(!kernel_space.page_table.translate(mid_rodata.floor()).unwrap().writable());
+    assert!(!kernel_space.page_table.translate(mid_data.floor()).unwrap().executable());
+    println!("remap_test passed!");
+}
@@ -6,8 +6,14 @@ mod memory_set;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum, VPNRange, StepByOne};
 pub use frame_allocator::{FrameTracker, frame_alloc};
-pub use page_table::{PageTableEntry, PageTable, PTEFlags};
+pub use page_table::{
+    translated_byte_buffer, translated_refmut, translated_str, translated_value, PageTableEntry,
+    PageTable, PTEFlags,
+};
+pub use memory_set::{remap_test, MapPermission, MemorySet, KERNEL_SPACE};
 
 pub fn init() {
-    heap_allocator::init_heap();
+    heap_allocator::init_heap(); //全局动态内存分配器的初始化
+    frame_allocator::init_frame_allocator(); //物理页帧管理器的初始化
+    KERNEL_SPACE.exclusive_access().activate(); //创建内核地址空间并开启分页
 }
\ No newline at end of file
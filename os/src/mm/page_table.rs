@@ -1,8 +1,12 @@
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
+use core::mem::{align_of, size_of, MaybeUninit};
 
-use super::{frame_alloc, FrameTracker, PhysPageNum, VirtPageNum};
+use crate::config::PAGE_SIZE;
+
+use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
 
 bitflags! {
     pub struct PTEFlags: u8 {
@@ -120,6 +124,18 @@ impl PageTable {
         assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
     }
+    //修改一个已经存在的键值对，用于改变页面映射到的物理页号或者访问权限
+    pub fn remap(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before remapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+    //只修改一个已经存在的键值对的标志位，物理页号保持不变
+    pub fn set_flags(&mut self, vpn: VirtPageNum, flags: PTEFlags) {
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before setting flags", vpn);
+        *pte = PageTableEntry::new(pte.ppn(), flags | PTEFlags::V);
+    }
     //通过索引，删除一个键值对
     pub fn unmap(&self, vpn: VirtPageNum) {
         let pte = self.find_pte(vpn).unwrap();
@@ -138,4 +154,122 @@ impl PageTable {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.find_pte(vpn).map(|pte| *pte)
     }
+
+    //将虚拟地址转换为物理地址，保留页内偏移
+    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
+        self.find_pte(va.floor()).map(|pte| {
+            let aligned_pa: PhysAddr = pte.ppn().into();
+            let offset = va.page_offset();
+            let aligned_pa_usize: usize = aligned_pa.into();
+            (aligned_pa_usize + offset).into()
+        })
+    }
+
+    //构造 Sv39 模式的 satp ：MODE 字段为 8 ，低44位为根物理页号
+    pub fn token(&self) -> usize {
+        8usize << 60 | self.root_ppn.0
+    }
+}
+
+//应用地址空间只使用 Sv39 的低半部分，不低于这个值的地址不可能是合法的用户地址
+const USER_SPACE_END: usize = 1 << 38;
+
+//应用通过系统调用传入的地址所在的页面：必须已经映射并且带有 U 标志位，write 为 true 时还必须可写
+//不满足时返回 None ，这样应用既不能让内核访问没有映射的地址，也不能借内核之手读写 Trap 上下文之类的页面
+fn translate_user_page(page_table: &PageTable, vpn: VirtPageNum, write: bool) -> Option<PhysPageNum> {
+    let pte = page_table.translate(vpn)?;
+    let accessible = pte.is_valid()
+        && pte.flags().contains(PTEFlags::U)
+        && if write { pte.writable() } else { pte.readable() };
+    if accessible {
+        Some(pte.ppn())
+    } else {
+        None
+    }
+}
+
+//将应用地址空间中一个缓冲区转化为在内核空间中能够直接访问的形式
+//缓冲区可能跨越多个物理页帧，所以返回每一段的可变切片
+//write 表示内核是否要写入缓冲区，缓冲区中有应用不能以这种方式访问的页面时返回 None
+pub fn translated_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    write: bool,
+) -> Option<Vec<&'static mut [u8]>> {
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr as usize;
+    let end = start.checked_add(len)?;
+    if end > USER_SPACE_END {
+        return None;
+    }
+    let mut v = Vec::new();
+    while start < end {
+        let start_va = VirtAddr::from(start);
+        let mut vpn = start_va.floor();
+        let ppn = translate_user_page(&page_table, vpn, write)?;
+        vpn.step();
+        let mut end_va: VirtAddr = vpn.into();
+        end_va = end_va.min(VirtAddr::from(end)); //本页结束和缓冲区结束取较小者
+        if end_va.page_offset() == 0 {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
+        } else {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+        }
+        start = end_va.into();
+    }
+    Some(v)
+}
+
+//从应用地址空间中读取一个以 \0 结尾的字符串，遇到应用不能读取的地址时返回 None
+pub fn translated_str(token: usize, ptr: *const u8) -> Option<String> {
+    let page_table = PageTable::from_token(token);
+    let mut string = String::new();
+    let mut va = ptr as usize;
+    loop {
+        if va >= USER_SPACE_END {
+            return None;
+        }
+        let user_va = VirtAddr::from(va);
+        let ppn = translate_user_page(&page_table, user_va.floor(), false)?;
+        let ch = ppn.get_bytes_array()[user_va.page_offset()];
+        if ch == 0 {
+            break;
+        }
+        string.push(ch as char);
+        va += 1;
+    }
+    Some(string)
+}
+
+//读取应用地址空间中一个类型为 T 的值，它可能跨越两个页面，应用不能读取时返回 None
+pub fn translated_value<T: Copy>(token: usize, ptr: *const T) -> Option<T> {
+    let buffers = translated_byte_buffer(token, ptr as *const u8, size_of::<T>(), false)?;
+    let mut value = MaybeUninit::<T>::uninit();
+    let bytes = value.as_mut_ptr() as *mut u8;
+    let mut offset = 0;
+    for buffer in buffers {
+        unsafe {
+            core::ptr::copy_nonoverlapping(buffer.as_ptr(), bytes.add(offset), buffer.len());
+        }
+        offset += buffer.len();
+    }
+    Some(unsafe { value.assume_init() })
+}
+
+//获取应用地址空间中一个类型为 T 的变量的可变引用
+//变量必须按 T 对齐、整个位于应用可写的同一个页面中，否则返回 None
+pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> Option<&'static mut T> {
+    let page_table = PageTable::from_token(token);
+    let va = ptr as usize;
+    if va % align_of::<T>() != 0 || va >= USER_SPACE_END {
+        return None;
+    }
+    let user_va = VirtAddr::from(va);
+    if user_va.page_offset() + size_of::<T>() > PAGE_SIZE {
+        return None;
+    }
+    let ppn = translate_user_page(&page_table, user_va.floor(), true)?;
+    let pa: usize = PhysAddr::from(ppn).into();
+    Some(PhysAddr::from(pa + user_va.page_offset()).get_mut())
 }
@@ -0,0 +1,43 @@
+use super::UPSafeCell;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+//条件变量：总是和一个互斥锁一起使用
+pub struct Condvar {
+    pub inner: UPSafeCell<CondvarInner>,
+}
+
+pub struct CondvarInner {
+    pub wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(CondvarInner {
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    //唤醒一个等待的线程，没有线程在等待时什么也不做
+    pub fn signal(&self) {
+        let mut inner = self.inner.exclusive_access();
+        if let Some(task) = inner.wait_queue.pop_front() {
+            drop(inner);
+            wakeup_task(task);
+        }
+    }
+
+    //阻塞直到被 signal 唤醒
+    //调用者负责在等待前释放互斥锁、被唤醒后重新获取它，以便同时更新死锁检测器
+    pub fn wait(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.wait_queue.push_back(current_task().unwrap());
+        drop(inner);
+        block_current_and_run_next();
+    }
+}
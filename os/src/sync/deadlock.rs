@@ -0,0 +1,134 @@
+//! 基于银行家算法的死锁检测
+//!
+//! 按 tid 记录每个线程已经持有（Allocation）和正在申请（Need）的互斥锁与信号量资源，
+//! 以及每种资源的剩余数量（Available）。启用检测后，如果满足一个申请之后系统将处于不安全状态，
+//! 这次申请直接失败而不是让线程阻塞。
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+//死锁检测关心的资源
+#[derive(Clone, Copy)]
+pub enum Resource {
+    Mutex(usize),
+    Semaphore(usize),
+}
+
+impl Resource {
+    //在矩阵中的列号：互斥锁和信号量交错排列
+    fn column(self) -> usize {
+        match self {
+            Resource::Mutex(id) => id * 2,
+            Resource::Semaphore(id) => id * 2 + 1,
+        }
+    }
+}
+
+pub struct DeadlockDetector {
+    pub enabled: bool,
+    available: Vec<usize>, //每种资源剩余的数量
+    allocation: Vec<Vec<usize>>, //allocation[tid][res] 线程已经持有的资源数量
+    need: Vec<Vec<usize>>, //need[tid][res] 线程正在申请、还没有得到的资源数量
+}
+
+impl DeadlockDetector {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            available: Vec::new(),
+            allocation: Vec::new(),
+            need: Vec::new(),
+        }
+    }
+
+    //保证矩阵至少包含 tid 这一行和 column 这一列
+    fn reserve(&mut self, tid: usize, column: usize) {
+        if self.available.len() <= column {
+            self.available.resize(column + 1, 0);
+        }
+        let columns = self.available.len();
+        while self.allocation.len() <= tid {
+            self.allocation.push(Vec::new());
+            self.need.push(Vec::new());
+        }
+        for row in self.allocation.iter_mut().chain(self.need.iter_mut()) {
+            row.resize(columns, 0);
+        }
+    }
+
+    //创建了一个资源，初始数量为 count
+    pub fn add_resource(&mut self, res: Resource, count: usize) {
+        let column = res.column();
+        self.reserve(0, column);
+        self.available[column] = count;
+    }
+
+    //线程 tid 申请一个 res 资源；启用检测且申请会导致不安全状态时撤销申请并返回 false
+    pub fn request(&mut self, tid: usize, res: Resource) -> bool {
+        let column = res.column();
+        self.reserve(tid, column);
+        self.need[tid][column] += 1;
+        if self.enabled && !self.is_safe() {
+            self.need[tid][column] -= 1;
+            return false;
+        }
+        true
+    }
+
+    //线程 tid 得到了它申请的 res 资源
+    pub fn acquire(&mut self, tid: usize, res: Resource) {
+        let column = res.column();
+        self.reserve(tid, column);
+        //得到资源之前一定先调用过 request ，资源也一定还有剩余
+        debug_assert!(self.need[tid][column] > 0 && self.available[column] > 0);
+        self.need[tid][column] -= 1;
+        self.allocation[tid][column] += 1;
+        self.available[column] -= 1;
+    }
+
+    //线程 tid 释放了一个 res 资源；信号量可以由没有持有它的线程释放
+    pub fn release(&mut self, tid: usize, res: Resource) {
+        let column = res.column();
+        self.reserve(tid, column);
+        if self.allocation[tid][column] > 0 {
+            self.allocation[tid][column] -= 1;
+        } else {
+            //只有信号量可以由没有持有它的线程释放，互斥锁的持有者已经在解锁时检查过
+            debug_assert!(matches!(res, Resource::Semaphore(_)));
+        }
+        self.available[column] += 1;
+    }
+
+    //线程 tid 退出了：清空它持有和申请的记录，以免复用这个 tid 的新线程继承它们
+    //退出时没有释放的互斥锁不会被自动解锁，所以这些资源也不会回到 Available 中
+    pub fn clear_thread(&mut self, tid: usize) {
+        if tid < self.allocation.len() {
+            self.allocation[tid].fill(0);
+            self.need[tid].fill(0);
+        }
+    }
+
+    //安全性检查：能否找到一个序列，让所有线程依次得到它们申请的资源并最终释放已经持有的资源
+    fn is_safe(&self) -> bool {
+        let mut work = self.available.clone();
+        let mut finish = vec![false; self.need.len()];
+        loop {
+            let next = (0..self.need.len()).find(|&tid| {
+                !finish[tid]
+                    && self.need[tid]
+                        .iter()
+                        .zip(work.iter())
+                        .all(|(need, work)| need <= work)
+            });
+            match next {
+                Some(tid) => {
+                    for (work, allocation) in work.iter_mut().zip(self.allocation[tid].iter()) {
+                        *work += allocation;
+                    }
+                    finish[tid] = true;
+                }
+                None => return finish.iter().all(|finished| *finished),
+            }
+        }
+    }
+}
@@ -1,3 +1,11 @@
+mod condvar;
+mod deadlock;
+mod mutex;
+mod semaphore;
 mod up;
 
-pub use up::UPSafeCell;
\ No newline at end of file
+pub use condvar::Condvar;
+pub use deadlock::{DeadlockDetector, Resource};
+pub use mutex::{Mutex, MutexBlocking, MutexSpin};
+pub use semaphore::Semaphore;
+pub use up::UPSafeCell;
@@ -0,0 +1,107 @@
+use super::UPSafeCell;
+use crate::task::{
+    block_current_and_run_next, current_task, suspend_current_and_run_next, wakeup_task,
+    TaskControlBlock,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+//提供给用户程序的互斥锁
+//tid 是调用者的线程编号，用来记录和检查锁的持有者
+pub trait Mutex: Sync + Send {
+    fn lock(&self, tid: usize);
+    //互斥锁没有被锁上或者调用者不是持有者时返回 false
+    fn unlock(&self, tid: usize) -> bool;
+}
+
+//自旋锁：拿不到锁时让出 CPU ，下次被调度时再尝试
+pub struct MutexSpin {
+    owner: UPSafeCell<Option<usize>>, //持有锁的线程，没有被锁上时为 None
+}
+
+impl MutexSpin {
+    pub fn new() -> Self {
+        Self {
+            owner: unsafe { UPSafeCell::new(None) },
+        }
+    }
+}
+
+impl Mutex for MutexSpin {
+    fn lock(&self, tid: usize) {
+        loop {
+            let mut owner = self.owner.exclusive_access();
+            if owner.is_some() {
+                drop(owner);
+                suspend_current_and_run_next();
+                continue;
+            } else {
+                *owner = Some(tid);
+                return;
+            }
+        }
+    }
+
+    fn unlock(&self, tid: usize) -> bool {
+        let mut owner = self.owner.exclusive_access();
+        if *owner != Some(tid) {
+            return false;
+        }
+        *owner = None;
+        true
+    }
+}
+
+//阻塞锁：拿不到锁时阻塞在等待队列上，直到持有者释放锁时把锁直接交给它
+pub struct MutexBlocking {
+    inner: UPSafeCell<MutexBlockingInner>,
+}
+
+pub struct MutexBlockingInner {
+    owner: Option<usize>, //持有锁的线程，没有被锁上时为 None
+    wait_queue: VecDeque<(usize, Arc<TaskControlBlock>)>, //等待的线程和它的 tid
+}
+
+impl MutexBlocking {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(MutexBlockingInner {
+                    owner: None,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+}
+
+impl Mutex for MutexBlocking {
+    fn lock(&self, tid: usize) {
+        let mut mutex_inner = self.inner.exclusive_access();
+        if mutex_inner.owner.is_some() {
+            mutex_inner
+                .wait_queue
+                .push_back((tid, current_task().unwrap()));
+            drop(mutex_inner);
+            block_current_and_run_next();
+        } else {
+            mutex_inner.owner = Some(tid);
+        }
+    }
+
+    fn unlock(&self, tid: usize) -> bool {
+        let mut mutex_inner = self.inner.exclusive_access();
+        if mutex_inner.owner != Some(tid) {
+            return false;
+        }
+        //有线程在等待时锁保持锁上的状态，直接转交给队头的线程
+        if let Some((waking_tid, waking_task)) = mutex_inner.wait_queue.pop_front() {
+            mutex_inner.owner = Some(waking_tid);
+            drop(mutex_inner);
+            wakeup_task(waking_task);
+        } else {
+            mutex_inner.owner = None;
+        }
+        true
+    }
+}
@@ -18,4 +18,8 @@ impl<T> UPSafeCell<T> {
     pub fn exclusive_access(&self) -> RefMut<'_, T> {
         self.inner.borrow_mut()
     }
+    //尝试独占访问，已经被借用时返回 None 而不是 panic（用于出错时打印诊断信息）
+    pub fn try_exclusive_access(&self) -> Option<RefMut<'_, T>> {
+        self.inner.try_borrow_mut().ok()
+    }
 }
\ No newline at end of file
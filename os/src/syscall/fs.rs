@@ -1,19 +1,97 @@
+use crate::mm::translated_byte_buffer;
+use crate::task::{
+    block_current_and_run_next, current_task, current_user_token, prepare_user_read,
+    prepare_user_write,
+};
+use crate::tty::TTY;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const FD_STDIN: usize = 0;
 const FD_STDOUT: usize = 1;
 
+//目前只支持标准输出，其他 fd 返回 -1
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     match fd {
         FD_STDOUT => {
-            let slice = unsafe {
-                //接受一个指向某个内存区域的原始指针buf和一个长度len，并返回一个指向这个内存区域的切片。
-                core::slice::from_raw_parts(buf, len)
+            //buf 是应用地址空间中的虚拟地址，需要先通过应用的页表转换成内核能访问的切片
+            prepare_user_read(buf as usize, len);
+            let buffers = match translated_byte_buffer(current_user_token(), buf, len, false) {
+                Some(buffers) => buffers,
+                None => return -1,
             };
-            //它接受一个字节切片，并尝试将其解释为一个UTF-8字符串
-            let str = core::str::from_utf8(slice).unwrap(); 
-            print!("{}", str);
+            //一个多字节字符可能被页边界切成两半，所以先把各段拼接起来再按 UTF-8 解码
+            let mut data = Vec::with_capacity(len);
+            for buffer in buffers {
+                data.extend_from_slice(buffer);
+            }
+            //不合法的 UTF-8 序列输出为替换字符，而不是让内核 panic
+            print!("{}", String::from_utf8_lossy(&data));
             len as isize
         },
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+        _ => -1,
+    }
+}
+
+//从标准输入读取，数据经过 TTY 行规程处理
+//还没有可读的数据时阻塞当前任务，等时钟中断中的 check_input 发现有输入后再唤醒它
+pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
+    match fd {
+        FD_STDIN => {
+            if len == 0 {
+                return 0;
+            }
+            //阻塞之前先检查缓冲区，避免读走输入之后才发现无处存放
+            prepare_user_write(buf as usize, len);
+            if translated_byte_buffer(current_user_token(), buf, len, true).is_none() {
+                return -1;
+            }
+            let data = loop {
+                let mut tty = TTY.exclusive_access();
+                tty.poll();
+                if tty.readable() {
+                    break tty.read(len);
+                }
+                tty.add_reader(current_task().unwrap());
+                drop(tty);
+                block_current_and_run_next();
+            };
+            //用户缓冲区可能跨越多个物理页帧；阻塞期间其他线程可能修改了地址空间，需要重新转换
+            prepare_user_write(buf as usize, data.len());
+            let buffers = match translated_byte_buffer(current_user_token(), buf, data.len(), true) {
+                Some(buffers) => buffers,
+                None => return -1,
+            };
+            let mut start = 0;
+            for buffer in buffers {
+                buffer.copy_from_slice(&data[start..start + buffer.len()]);
+                start += buffer.len();
+            }
+            data.len() as isize
+        },
+        _ => -1,
+    }
+}
+
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+
+//读取或设置标准输入的 TTY 模式
+//与 Linux 不同，arg 直接是模式标志位（TTY_ICANON/TTY_ECHO）而不是 termios 结构体的地址
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    if fd != FD_STDIN {
+        return -1;
+    }
+    let mut tty = TTY.exclusive_access();
+    match request {
+        TCGETS => tty.flags() as isize,
+        TCSETS => {
+            if tty.set_flags(arg) {
+                0
+            } else {
+                -1
+            }
         }
+        _ => -1,
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,94 @@
+use crate::config::{PAGE_SIZE, USER_STACK_BASE};
+use crate::mm::{MapPermission, VirtAddr};
+use crate::task::current_process;
+
+//prot 的低 3 位依次表示可读、可写、可执行，其他位必须为 0 ，且至少要有一种访问方式
+//只写不读在 RISC-V 的页表项中是保留的组合，同样不允许
+fn prot_to_permission(prot: usize) -> Option<MapPermission> {
+    if prot & !0x7 != 0 || prot & 0x7 == 0 || prot & 0x3 == 0x2 {
+        return None;
+    }
+    Some(MapPermission::from_bits((prot << 1) as u8).unwrap() | MapPermission::U)
+}
+
+//检查 [start, start + len) 是不是一个起点按页对齐、长度不为 0 、位于用户栈区域下面的区间，返回它的结束地址
+fn user_range_end(start: usize, len: usize) -> Option<usize> {
+    if start % PAGE_SIZE != 0 || len == 0 {
+        return None;
+    }
+    start.checked_add(len).filter(|&end| end <= USER_STACK_BASE)
+}
+
+//把堆顶移动 size 字节（可以为负），返回原来的堆顶，失败时返回 -1
+//堆扩展的部分同样在第一次被访问时才分配页帧
+pub fn sys_sbrk(size: isize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.change_program_brk(size) {
+        Some(old_brk) => old_brk as isize,
+        None => -1,
+    }
+}
+
+//在 [start, start + len) 映射一段匿名内存，len 向上取整到页面大小，页面在第一次被访问时才分配
+//参数不合法或者和已有的映射重叠时返回 -1
+pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
+    let (end, permission) = match (user_range_end(start, len), prot_to_permission(prot)) {
+        (Some(end), Some(permission)) => (end, permission),
+        _ => return -1,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner
+        .memory_set
+        .mmap(VirtAddr::from(start), VirtAddr::from(end), permission)
+    {
+        0
+    } else {
+        -1
+    }
+}
+
+//把 [start, start + len) 的访问权限改为 prot ，len 向上取整到页面大小，可以只修改一段映射的一部分
+//参数不合法、区间中有没有被映射的页面或者区间和堆有交集时返回 -1
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    let (end, permission) = match (user_range_end(start, len), prot_to_permission(prot)) {
+        (Some(end), Some(permission)) => (end, permission),
+        _ => return -1,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.overlaps_heap(start, end) {
+        return -1;
+    }
+    if inner
+        .memory_set
+        .mprotect(VirtAddr::from(start), VirtAddr::from(end), permission)
+    {
+        0
+    } else {
+        -1
+    }
+}
+
+//解除 [start, start + len) 的映射，len 向上取整到页面大小，可以只解除一段映射的一部分
+//参数不合法、区间中有没有被映射的页面或者区间和堆有交集时返回 -1
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    let end = match user_range_end(start, len) {
+        Some(end) => end,
+        None => return -1,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.overlaps_heap(start, end) {
+        return -1;
+    }
+    if inner
+        .memory_set
+        .munmap(VirtAddr::from(start), VirtAddr::from(end))
+    {
+        0
+    } else {
+        -1
+    }
+}
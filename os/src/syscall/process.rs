@@ -1,18 +1,257 @@
-use crate::task::suspend_current_and_run_next;
-use crate::task::exit_current_and_run_next;
-use crate::timer::get_time_ms;
+use crate::config::{BIG_STRIDE, MAX_SYSCALL_NUM, MAX_TIME_SLICE};
+use crate::loader::get_app_data_by_name;
+use crate::mm::{translated_byte_buffer, translated_refmut, translated_str, translated_value};
+use crate::task::{
+    add_task, block_current_and_run_next, current_process, current_task, current_user_token,
+    exit_current_and_run_next, pid2process, prepare_user_read, prepare_user_str,
+    prepare_user_write, process_created,
+    remove_from_pid2process, yield_current_and_run_next, TaskStatus,
+};
+use crate::timer::{
+    add_timer, get_mtime, get_time_ms, ms_to_mtime, mtime_to_ms, set_next_trigger, us_to_mtime,
+};
+use alloc::alloc::{alloc_zeroed, Layout};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::mem::size_of;
 
 pub fn sys_exit(exit_code: i32) -> ! {
     println!("[kernel] Application exited with code {}", exit_code);
-    exit_current_and_run_next(); //退出当前的应用并切换到下个应用
+    exit_current_and_run_next(exit_code); //退出当前的应用并切换到下个应用
     panic!("Unreachable in sys_exit!");
 }
 
 pub fn sys_yield() -> isize {
-    suspend_current_and_run_next(); //暂停当前的应用并切换到下个应用。
+    yield_current_and_run_next(); //暂停当前的应用并切换到下个应用。
     0
 }
 
 pub fn sys_get_time() -> isize {
     get_time_ms() as isize //以ms为单位返回当前计数器的值
-}
\ No newline at end of file
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+//阻塞当前任务直到 mtime 达到 expire
+fn sleep_until(expire: usize) {
+    add_timer(expire, current_task().unwrap());
+    //截止时间可能早于已经设置好的下一次时钟中断，需要重新设置
+    set_next_trigger();
+    block_current_and_run_next();
+}
+
+//睡眠 ms 毫秒，截止时间溢出时取 usize::MAX ，即一直睡眠
+pub fn sys_sleep(ms: usize) -> isize {
+    sleep_until(get_mtime().saturating_add(ms_to_mtime(ms)));
+    0
+}
+
+//睡眠 req 指定的时长，req 不可读或者时长不合法时返回 -1
+pub fn sys_nanosleep(req: *const TimeVal) -> isize {
+    let token = current_user_token();
+    prepare_user_read(req as usize, size_of::<TimeVal>());
+    let req = match translated_value(token, req) {
+        Some(req) => req,
+        None => return -1,
+    };
+    if req.usec >= 1_000_000 {
+        return -1;
+    }
+    //usec 小于 10^6 ，换算时不会溢出；sec 来自用户，截止时间溢出时取 usize::MAX
+    let duration = ms_to_mtime(req.sec.saturating_mul(1000)).saturating_add(us_to_mtime(req.usec));
+    sleep_until(get_mtime().saturating_add(duration));
+    0
+}
+
+pub fn sys_getpid() -> isize {
+    current_process().getpid() as isize
+}
+
+//子进程返回 0 ，父进程返回子进程的 pid ，当前进程还有其他线程在运行时返回 -1
+pub fn sys_fork() -> isize {
+    let current_process = current_process();
+    if current_process.inner_exclusive_access().alive_thread_count() != 1 {
+        return -1;
+    }
+    let new_process = current_process.fork();
+    let new_pid = new_process.getpid();
+    // modify trap context of new_task, because it returns immediately after switching
+    let new_task = new_process.inner_exclusive_access().get_task(0);
+    let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
+    // we do not have to move to next instruction since we have done it before
+    // for child process, fork returns 0
+    trap_cx.x[10] = 0;
+    // add new task to scheduler
+    process_created(&new_process);
+    add_task(new_task);
+    new_pid as isize
+}
+
+//path 是应用地址空间中以 \0 结尾的应用名，找不到应用或者当前进程还有其他线程在运行时返回 -1
+pub fn sys_exec(path: *const u8) -> isize {
+    if current_process().inner_exclusive_access().alive_thread_count() != 1 {
+        return -1;
+    }
+    let token = current_user_token();
+    prepare_user_str(path as usize);
+    let path = match translated_str(token, path) {
+        Some(path) => path,
+        None => return -1,
+    };
+    if let Some(data) = get_app_data_by_name(path.as_str()) {
+        let process = current_process();
+        process.exec(data);
+        0
+    } else {
+        -1
+    }
+}
+
+/// If there is not a child process whose pid is same as given, return -1.
+/// Else if there is a child process but it is still running, return -2.
+//pid 为 -1 表示等待任意一个子进程；exit_code_ptr 不可写时返回 -1 ，子进程不会被回收
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+    let process = current_process();
+    // find a child process
+
+    // ---- access current PCB exclusively
+    let mut inner = process.inner_exclusive_access();
+    if !inner
+        .children
+        .iter()
+        .any(|p| pid == -1 || pid as usize == p.getpid())
+    {
+        return -1;
+        // ---- release current PCB
+    }
+    let pair = inner.children.iter().enumerate().find(|(_, p)| {
+        // ++++ temporarily access child PCB exclusively
+        p.inner_exclusive_access().is_zombie && (pid == -1 || pid as usize == p.getpid())
+        // ++++ release child PCB
+    });
+    if let Some((idx, _)) = pair {
+        //先检查退出码的地址，再回收子进程
+        inner
+            .memory_set
+            .prepare_access(exit_code_ptr as usize, size_of::<i32>(), true);
+        let exit_code_ref = match translated_refmut(inner.memory_set.token(), exit_code_ptr) {
+            Some(exit_code_ref) => exit_code_ref,
+            None => return -1,
+        };
+        let child = inner.children.remove(idx);
+        remove_from_pid2process(child.getpid());
+        // confirm that child will be deallocated after being removed from children list
+        //此时子进程只被这里引用，离开作用域后 pid 、内核栈和页表都会被回收
+        assert_eq!(Arc::strong_count(&child), 1);
+        let found_pid = child.getpid();
+        // ++++ temporarily access child PCB exclusively
+        let exit_code = child.inner_exclusive_access().exit_code;
+        // ++++ release child PCB
+        *exit_code_ref = exit_code;
+        found_pid as isize
+    } else {
+        -2
+    }
+    // ---- release current PCB automatically
+}
+
+//设置当前线程的优先级，优先级必须在 [2, BIG_STRIDE] 之间，成功时返回设置的优先级，否则返回 -1
+//优先级超过 BIG_STRIDE 时步长为 0 ，行程值不再增长，其他任务会被饿死
+pub fn sys_set_priority(prio: isize) -> isize {
+    if prio < 2 || prio as usize > BIG_STRIDE {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().priority = prio as usize;
+    prio
+}
+
+//设置当前线程的时间片长度（ms），必须在 [1, MAX_TIME_SLICE] 内，从下一次被调度开始生效
+//成功时返回设置的长度，否则返回 -1
+pub fn sys_set_time_slice(ms: isize) -> isize {
+    if ms <= 0 || ms as usize > MAX_TIME_SLICE {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().time_slice = ms as usize;
+    ms
+}
+
+//sys_task_info 返回给用户程序的进程信息，布局需要和用户库中的定义保持一致
+//统计信息是进程中所有还没有被回收的线程的总和
+#[repr(C)]
+pub struct TaskInfo {
+    pub status: TaskStatus, //进程已退出时为 Zombie ，否则为主线程的状态
+    pub syscall_times: [u32; MAX_SYSCALL_NUM], //每个系统调用被调用的次数
+    pub time: usize, //从第一次被调度到现在经过的时间（ms），还没被调度过时为 0
+    pub user_time: usize, //在用户态累计运行的时间（ms）
+    pub kernel_time: usize, //在内核态累计运行的时间（ms）
+}
+
+//查询 pid 为 id 的进程（包括还没有被回收的僵尸进程）的统计信息，找不到进程或者 ti 不可写时返回 -1
+pub fn sys_task_info(id: usize, ti: *mut TaskInfo) -> isize {
+    let process = match pid2process(id) {
+        Some(process) => process,
+        None => return -1,
+    };
+    //TaskInfo 有 4KB 多，直接在堆上分配以免占用内核栈；全零的 TaskInfo 是合法的值
+    let info = unsafe { alloc_zeroed(Layout::new::<TaskInfo>()) } as *mut TaskInfo;
+    if info.is_null() {
+        return -1;
+    }
+    let mut info = unsafe { Box::from_raw(info) };
+    info.status = TaskStatus::Zombie;
+    let process_inner = process.inner_exclusive_access();
+    let (mut user_time, mut kernel_time) = (0, 0);
+    for (tid, task) in process_inner.tasks.iter().enumerate() {
+        let task = match task {
+            Some(task) => task,
+            None => continue,
+        };
+        let inner = task.inner_exclusive_access();
+        //进程从主线程第一次被调度开始计时
+        if tid == 0 {
+            if !process_inner.is_zombie {
+                info.status = inner.tasks_status;
+            }
+            info.time = inner
+                .first_time
+                .map(|first| mtime_to_ms(get_mtime() - first))
+                .unwrap_or(0);
+        }
+        for (total, times) in info.syscall_times.iter_mut().zip(inner.syscall_times.iter()) {
+            *total += times;
+        }
+        user_time += inner.user_time;
+        kernel_time += inner.kernel_time;
+    }
+    info.user_time = mtime_to_ms(user_time);
+    info.kernel_time = mtime_to_ms(kernel_time);
+    //查询的可能就是当前进程，拷贝到用户空间前要先释放借用
+    drop(process_inner);
+    let info_bytes = unsafe {
+        core::slice::from_raw_parts(&*info as *const TaskInfo as *const u8, size_of::<TaskInfo>())
+    };
+    //TaskInfo 可能跨越多个页面，需要分段拷贝
+    prepare_user_write(ti as usize, size_of::<TaskInfo>());
+    let buffers = match translated_byte_buffer(
+        current_user_token(),
+        ti as *const u8,
+        size_of::<TaskInfo>(),
+        true,
+    ) {
+        Some(buffers) => buffers,
+        None => return -1,
+    };
+    let mut start = 0;
+    for buffer in buffers {
+        buffer.copy_from_slice(&info_bytes[start..start + buffer.len()]);
+        start += buffer.len();
+    }
+    0
+}
@@ -0,0 +1,227 @@
+use crate::sync::{Condvar, Mutex, MutexBlocking, MutexSpin, Resource, Semaphore};
+use crate::task::{current_process, current_task, ProcessControlBlock};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+//在空闲的位置（或表尾）放入一个对象，返回它的编号
+fn insert_object<T: ?Sized>(list: &mut Vec<Option<Arc<T>>>, object: Arc<T>) -> usize {
+    if let Some(id) = list.iter().position(|item| item.is_none()) {
+        list[id] = Some(object);
+        id
+    } else {
+        list.push(Some(object));
+        list.len() - 1
+    }
+}
+
+//编号为 id 的对象，不存在时返回 None
+fn get_object<T: ?Sized>(list: &[Option<Arc<T>>], id: usize) -> Option<Arc<T>> {
+    list.get(id).and_then(|item| item.as_ref().map(Arc::clone))
+}
+
+//启用死锁检测时，申请资源会导致不安全状态的返回值
+const DEADLOCK: isize = -0xDEAD;
+
+fn current_tid() -> usize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .tid
+}
+
+//enabled 为 1 时启用当前进程的死锁检测，为 0 时关闭，其他值返回 -1
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    if enabled > 1 {
+        return -1;
+    }
+    let process = current_process();
+    process.inner_exclusive_access().deadlock_detector.enabled = enabled == 1;
+    0
+}
+
+//创建一个互斥锁，blocking 为 false 时是自旋锁，返回它的编号
+pub fn sys_mutex_create(blocking: bool) -> isize {
+    let process = current_process();
+    let mutex: Arc<dyn Mutex> = if blocking {
+        Arc::new(MutexBlocking::new())
+    } else {
+        Arc::new(MutexSpin::new())
+    };
+    let mut process_inner = process.inner_exclusive_access();
+    let id = insert_object(&mut process_inner.mutex_list, mutex);
+    process_inner
+        .deadlock_detector
+        .add_resource(Resource::Mutex(id), 1);
+    id as isize
+}
+
+//线程 tid 获取编号为 mutex_id 的互斥锁并记录到死锁检测器中
+//启用死锁检测且加锁会导致不安全状态时返回 -0xDEAD
+fn lock_mutex(
+    process: &ProcessControlBlock,
+    tid: usize,
+    mutex_id: usize,
+    mutex: &Arc<dyn Mutex>,
+) -> isize {
+    let res = Resource::Mutex(mutex_id);
+    if !process
+        .inner_exclusive_access()
+        .deadlock_detector
+        .request(tid, res)
+    {
+        return DEADLOCK;
+    }
+    //加锁可能阻塞，调用时不能持有进程控制块的借用
+    mutex.lock(tid);
+    process
+        .inner_exclusive_access()
+        .deadlock_detector
+        .acquire(tid, res);
+    0
+}
+
+//线程 tid 释放编号为 mutex_id 的互斥锁并记录到死锁检测器中
+//互斥锁没有被锁上或者 tid 不是持有者时返回 -1
+fn unlock_mutex(
+    process: &ProcessControlBlock,
+    tid: usize,
+    mutex_id: usize,
+    mutex: &Arc<dyn Mutex>,
+) -> isize {
+    if !mutex.unlock(tid) {
+        return -1;
+    }
+    process
+        .inner_exclusive_access()
+        .deadlock_detector
+        .release(tid, Resource::Mutex(mutex_id));
+    0
+}
+
+//编号不存在时返回 -1 ，启用死锁检测且加锁会导致不安全状态时返回 -0xDEAD
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -1,
+    };
+    drop(process_inner);
+    lock_mutex(&process, tid, mutex_id, &mutex)
+}
+
+//编号不存在、互斥锁没有被锁上或者当前线程不是持有者时返回 -1
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -1,
+    };
+    drop(process_inner);
+    unlock_mutex(&process, tid, mutex_id, &mutex)
+}
+
+//创建一个初始资源数为 res_count 的信号量，返回它的编号
+//信号量用 isize 计数，res_count 超过 isize::MAX 时返回 -1
+pub fn sys_semaphore_create(res_count: usize) -> isize {
+    if res_count > isize::MAX as usize {
+        return -1;
+    }
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let id = insert_object(
+        &mut process_inner.semaphore_list,
+        Arc::new(Semaphore::new(res_count)),
+    );
+    process_inner
+        .deadlock_detector
+        .add_resource(Resource::Semaphore(id), res_count);
+    id as isize
+}
+
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -1,
+    };
+    process_inner
+        .deadlock_detector
+        .release(tid, Resource::Semaphore(sem_id));
+    drop(process_inner);
+    sem.up();
+    0
+}
+
+//启用死锁检测且申请会导致不安全状态时返回 -0xDEAD
+pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -1,
+    };
+    let res = Resource::Semaphore(sem_id);
+    if !process_inner.deadlock_detector.request(tid, res) {
+        return DEADLOCK;
+    }
+    drop(process_inner);
+    sem.down();
+    process
+        .inner_exclusive_access()
+        .deadlock_detector
+        .acquire(tid, res);
+    0
+}
+
+//创建一个条件变量，返回它的编号
+pub fn sys_condvar_create() -> isize {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    insert_object(&mut process_inner.condvar_list, Arc::new(Condvar::new())) as isize
+}
+
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let condvar = match get_object(&process_inner.condvar_list, condvar_id) {
+        Some(condvar) => condvar,
+        None => return -1,
+    };
+    drop(process_inner);
+    condvar.signal();
+    0
+}
+
+//释放编号为 mutex_id 的互斥锁并等待条件变量，被唤醒后重新获取互斥锁
+//编号不存在、互斥锁没有被锁上或者当前线程不是持有者时返回 -1 ；
+//启用死锁检测且重新加锁会导致不安全状态时返回 -0xDEAD ，此时线程不持有互斥锁
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let (condvar, mutex) = match (
+        get_object(&process_inner.condvar_list, condvar_id),
+        get_object(&process_inner.mutex_list, mutex_id),
+    ) {
+        (Some(condvar), Some(mutex)) => (condvar, mutex),
+        _ => return -1,
+    };
+    drop(process_inner);
+    //单处理器上内核不会被抢占，释放互斥锁之后、进入等待队列之前不会错过 signal
+    let ret = unlock_mutex(&process, tid, mutex_id, &mutex);
+    if ret != 0 {
+        return ret;
+    }
+    condvar.wait();
+    lock_mutex(&process, tid, mutex_id, &mutex)
+}
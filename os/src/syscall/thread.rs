@@ -0,0 +1,80 @@
+use crate::mm::KERNEL_SPACE;
+use crate::task::{add_task, current_task, TaskControlBlock};
+use crate::trap::{trap_handler, TrapContext};
+use alloc::sync::Arc;
+
+//在当前进程中创建一个从 entry 开始执行的线程，arg 通过 a0 传给它，返回新线程的 tid
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    let task = current_task().unwrap();
+    let process = task.process.upgrade().unwrap();
+    // create a new thread
+    let task_inner = task.inner_exclusive_access();
+    let new_task = Arc::new(TaskControlBlock::new(
+        Arc::clone(&process),
+        task_inner.res.as_ref().unwrap().ustack_base,
+        true,
+    ));
+    let mut new_task_inner = new_task.inner_exclusive_access();
+    new_task_inner.inherit_sched(&task_inner);
+    drop(task_inner);
+    let new_task_res = new_task_inner.res.as_ref().unwrap();
+    let new_task_tid = new_task_res.tid;
+    let new_task_trap_cx = new_task_inner.get_trap_cx();
+    *new_task_trap_cx = TrapContext::app_init_context(
+        entry,
+        new_task_res.ustack_top(),
+        KERNEL_SPACE.exclusive_access().token(),
+        new_task.kstack.get_top(),
+        trap_handler as usize,
+    );
+    new_task_trap_cx.x[10] = arg;
+    drop(new_task_inner);
+    // add new thread to current process
+    let mut process_inner = process.inner_exclusive_access();
+    let tasks = &mut process_inner.tasks;
+    while tasks.len() < new_task_tid + 1 {
+        tasks.push(None);
+    }
+    tasks[new_task_tid] = Some(Arc::clone(&new_task));
+    drop(process_inner);
+    // add new task to scheduler
+    add_task(new_task);
+    new_task_tid as isize
+}
+
+pub fn sys_gettid() -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .tid as isize
+}
+
+/// thread does not exist or is the current thread, return -1
+/// thread has not exited yet, return -2
+/// otherwise, return thread's exit code
+pub fn sys_waittid(tid: usize) -> i32 {
+    let task = current_task().unwrap();
+    let process = task.process.upgrade().unwrap();
+    let task_inner = task.inner_exclusive_access();
+    let mut process_inner = process.inner_exclusive_access();
+    // a thread cannot wait for itself
+    if task_inner.res.as_ref().unwrap().tid == tid {
+        return -1;
+    }
+    let waited_task = match process_inner.tasks.get(tid) {
+        Some(Some(waited_task)) => Arc::clone(waited_task),
+        _ => return -1,
+    };
+    let exit_code = waited_task.inner_exclusive_access().exit_code;
+    match exit_code {
+        Some(exit_code) => {
+            //线程控制块（包括内核栈）在这里被回收
+            process_inner.tasks[tid] = None;
+            exit_code
+        }
+        None => -2,
+    }
+}
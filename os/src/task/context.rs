@@ -1,3 +1,5 @@
+use crate::trap::trap_return;
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct TaskContext {
@@ -15,13 +17,11 @@ impl TaskContext {
         }
     }
 
-    pub fn goto_restore(kstack_ptr: usize) -> Self {
-        extern "C" {
-            fn __restore();
-        }
+    //第一次被调度时 ret 到 trap_return ，由它切换到应用地址空间并回到用户态
+    pub fn goto_trap_return(kstack_ptr: usize) -> Self {
         Self {
-            ra: __restore as usize, //ret返回后到__restore继续执行
-            sp: kstack_ptr, //trap上下文栈顶
+            ra: trap_return as usize, //ret返回后到trap_return继续执行
+            sp: kstack_ptr, //应用的内核栈栈顶
             s: [0; 12],
         }
     }
@@ -0,0 +1,196 @@
+use super::ProcessControlBlock;
+use crate::config::{kernel_stack_position, PAGE_SIZE, TRAP_CONTEXT, USER_STACK_SIZE};
+use crate::mm::{MapPermission, PhysPageNum, VirtAddr, KERNEL_SPACE};
+use crate::sync::UPSafeCell;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::arch::asm;
+use lazy_static::*;
+
+//编号分配器，pid 、内核栈编号和线程的 tid 都用它分配，和物理页帧分配器 StackFrameAllocator 的思路相同
+pub struct RecycleAllocator {
+    current: usize, //尚未分配过的最小编号
+    recycled: Vec<usize>, //回收的编号
+}
+
+impl RecycleAllocator {
+    pub fn new() -> Self {
+        RecycleAllocator {
+            current: 0,
+            recycled: Vec::new(),
+        }
+    }
+    //优先复用回收的编号
+    pub fn alloc(&mut self) -> usize {
+        if let Some(id) = self.recycled.pop() {
+            id
+        } else {
+            self.current += 1;
+            self.current - 1
+        }
+    }
+    pub fn dealloc(&mut self, id: usize) {
+        assert!(id < self.current);
+        assert!(
+            !self.recycled.iter().any(|i| *i == id),
+            "id {} has been deallocated!",
+            id
+        );
+        self.recycled.push(id);
+    }
+}
+
+lazy_static! {
+    static ref PID_ALLOCATOR: UPSafeCell<RecycleAllocator> =
+        unsafe { UPSafeCell::new(RecycleAllocator::new()) };
+    static ref KSTACK_ALLOCATOR: UPSafeCell<RecycleAllocator> =
+        unsafe { UPSafeCell::new(RecycleAllocator::new()) };
+}
+
+//和 FrameTracker 一样使用 RAII 的思想，PidHandle 被回收时 pid 也随之被回收
+pub struct PidHandle(pub usize);
+
+impl Drop for PidHandle {
+    fn drop(&mut self) {
+        PID_ALLOCATOR.exclusive_access().dealloc(self.0);
+    }
+}
+
+pub fn pid_alloc() -> PidHandle {
+    PidHandle(PID_ALLOCATOR.exclusive_access().alloc())
+}
+
+//线程的内核栈，创建时在内核地址空间中按内核栈编号映射一个 Framed 逻辑段，被回收时解除映射
+//内核栈编号和 pid 、tid 无关，每个线程都有自己的内核栈
+pub struct KernelStack(pub usize);
+
+pub fn kstack_alloc() -> KernelStack {
+    let kstack_id = KSTACK_ALLOCATOR.exclusive_access().alloc();
+    let (kstack_bottom, kstack_top) = kernel_stack_position(kstack_id);
+    KERNEL_SPACE.exclusive_access().insert_framed_area(
+        kstack_bottom.into(),
+        kstack_top.into(),
+        MapPermission::R | MapPermission::W,
+    );
+    KernelStack(kstack_id)
+}
+
+impl KernelStack {
+    //内核栈栈顶
+    pub fn get_top(&self) -> usize {
+        let (_, kernel_stack_top) = kernel_stack_position(self.0);
+        kernel_stack_top
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let (kernel_stack_bottom, _) = kernel_stack_position(self.0);
+        let kernel_stack_bottom_va: VirtAddr = kernel_stack_bottom.into();
+        KERNEL_SPACE
+            .exclusive_access()
+            .remove_area_with_start_vpn(kernel_stack_bottom_va.into());
+        //同一个编号以后会被复用，映射到新的物理页帧前要清掉快表中旧的映射
+        unsafe {
+            asm!("sfence.vma");
+        }
+        KSTACK_ALLOCATOR.exclusive_access().dealloc(self.0);
+    }
+}
+
+//线程在用户地址空间中的资源：tid 、用户栈和 Trap 上下文所在的页面
+pub struct TaskUserRes {
+    pub tid: usize,
+    pub ustack_base: usize, //所有线程用户栈所在区域的起始地址，即 USER_STACK_BASE
+    pub process: Weak<ProcessControlBlock>,
+}
+
+//tid 号线程的 Trap 上下文页面：从 TRAP_CONTEXT 开始往下依次排列
+fn trap_cx_bottom_from_tid(tid: usize) -> usize {
+    TRAP_CONTEXT - tid * PAGE_SIZE
+}
+
+//tid 号线程的用户栈：从 ustack_base 开始往上依次排列，每个用户栈下面有一个保护页
+fn ustack_bottom_from_tid(ustack_base: usize, tid: usize) -> usize {
+    ustack_base + tid * (PAGE_SIZE + USER_STACK_SIZE)
+}
+
+impl TaskUserRes {
+    pub fn new(
+        process: Arc<ProcessControlBlock>,
+        ustack_base: usize,
+        alloc_user_res: bool,
+    ) -> Self {
+        let tid = process.inner_exclusive_access().alloc_tid();
+        let task_user_res = Self {
+            tid,
+            ustack_base,
+            process: Arc::downgrade(&process),
+        };
+        if alloc_user_res {
+            task_user_res.alloc_user_res();
+        }
+        task_user_res
+    }
+    //在进程的地址空间中映射用户栈和 Trap 上下文
+    pub fn alloc_user_res(&self) {
+        let process = self.process.upgrade().unwrap();
+        let mut process_inner = process.inner_exclusive_access();
+        // alloc user stack
+        let ustack_bottom = ustack_bottom_from_tid(self.ustack_base, self.tid);
+        let ustack_top = ustack_bottom + USER_STACK_SIZE;
+        //用户栈通常只用到靠近栈顶的一小部分，按需分配
+        process_inner.memory_set.insert_lazy_area(
+            ustack_bottom.into(),
+            ustack_top.into(),
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        );
+        // alloc trap_cx
+        let trap_cx_bottom = trap_cx_bottom_from_tid(self.tid);
+        let trap_cx_top = trap_cx_bottom + PAGE_SIZE;
+        process_inner.memory_set.insert_framed_area(
+            trap_cx_bottom.into(),
+            trap_cx_top.into(),
+            MapPermission::R | MapPermission::W,
+        );
+    }
+    //解除用户栈和 Trap 上下文的映射，并回收 tid
+    fn dealloc_user_res(&self, process: &ProcessControlBlock) {
+        let mut process_inner = process.inner_exclusive_access();
+        let ustack_bottom_va: VirtAddr = ustack_bottom_from_tid(self.ustack_base, self.tid).into();
+        process_inner
+            .memory_set
+            .remove_area_with_start_vpn(ustack_bottom_va.into());
+        let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(self.tid).into();
+        process_inner
+            .memory_set
+            .remove_area_with_start_vpn(trap_cx_bottom_va.into());
+        process_inner.dealloc_tid(self.tid);
+    }
+    pub fn trap_cx_user_va(&self) -> usize {
+        trap_cx_bottom_from_tid(self.tid)
+    }
+    //Trap 上下文实际所在物理页帧的物理页号
+    pub fn trap_cx_ppn(&self) -> PhysPageNum {
+        let process = self.process.upgrade().unwrap();
+        let process_inner = process.inner_exclusive_access();
+        let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(self.tid).into();
+        process_inner
+            .memory_set
+            .translate(trap_cx_bottom_va.into())
+            .unwrap()
+            .ppn()
+    }
+    pub fn ustack_top(&self) -> usize {
+        ustack_bottom_from_tid(self.ustack_base, self.tid) + USER_STACK_SIZE
+    }
+}
+
+impl Drop for TaskUserRes {
+    fn drop(&mut self) {
+        //进程已经被回收时地址空间也已经不在了，不需要再解除映射
+        if let Some(process) = self.process.upgrade() {
+            self.dealloc_user_res(&process);
+        }
+    }
+}
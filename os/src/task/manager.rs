@@ -0,0 +1,85 @@
+use super::scheduler::{Scheduler, SchedulerImpl};
+use super::{ProcessControlBlock, TaskControlBlock};
+use crate::sync::UPSafeCell;
+#[cfg(feature = "tickless")]
+use crate::timer::resume_time_slice;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use lazy_static::*;
+
+//任务管理器只负责管理所有就绪的任务，正在运行的任务交给处理器管理
+//下一个运行哪个任务由编译时选定的调度器决定
+pub struct TaskManager {
+    scheduler: SchedulerImpl,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            scheduler: SchedulerImpl::new(),
+        }
+    }
+    //将一个任务交给调度器
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.scheduler.add(task);
+    }
+    //由调度器选出下一个任务
+    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.scheduler.fetch()
+    }
+}
+
+lazy_static! {
+    pub static ref TASK_MANAGER: UPSafeCell<TaskManager> = unsafe {
+        UPSafeCell::new(TaskManager::new())
+    };
+    //pid 到进程的映射，包括还没有被回收的僵尸进程，用于按 pid 查询进程
+    pub static ref PID2PCB: UPSafeCell<BTreeMap<usize, Arc<ProcessControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add(task);
+    //当前任务可能因为没有其他任务可运行而暂停了时间片计时，现在需要恢复
+    #[cfg(feature = "tickless")]
+    resume_time_slice();
+}
+
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch()
+}
+
+pub fn pid2process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
+    PID2PCB.exclusive_access().get(&pid).cloned()
+}
+
+pub fn insert_into_pid2process(pid: usize, process: Arc<ProcessControlBlock>) {
+    PID2PCB.exclusive_access().insert(pid, process);
+}
+
+pub fn remove_from_pid2process(pid: usize) {
+    if PID2PCB.exclusive_access().remove(&pid).is_none() {
+        panic!("cannot find pid {} in pid2process!", pid);
+    }
+}
+
+//就绪队列中是否有任务
+#[cfg(feature = "tickless")]
+pub fn has_ready_task() -> bool {
+    !TASK_MANAGER.exclusive_access().scheduler.is_empty()
+}
+
+//由调度器决定 task 这次被调度后的时间片长度（ms）
+pub fn time_slice_of(task: &Arc<TaskControlBlock>) -> Option<usize> {
+    TASK_MANAGER.exclusive_access().scheduler.time_slice(task)
+}
+
+//正在运行的任务用完了时间片，返回是否应该抢占它
+pub fn tick_task(task: &Arc<TaskControlBlock>) -> bool {
+    TASK_MANAGER.exclusive_access().scheduler.on_tick(task)
+}
+
+//正在运行的任务主动让出 CPU
+pub fn yield_task(task: &Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().scheduler.on_yield(task);
+}
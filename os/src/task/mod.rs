@@ -1,151 +1,257 @@
 mod context;
+mod id;
+mod manager;
+mod process;
+mod processor;
+mod scheduler;
 mod switch;
 //抑制 Clippy 的警告:同名的嵌套模块
 //#[allow(clippy::module_inception)]
 mod task;
 
-use crate::loader::{get_num_app, init_app_cx};
-use crate::sbi::shutdown;
-use crate::{config::MAX_APP_NUM, sync::UPSafeCell};
+use crate::loader::get_app_data_by_name;
+use crate::mm::VirtAddr;
+use crate::sync::UPSafeCell;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use context::TaskContext;
-use switch::__switch;
 use lazy_static::*;
-use task::{TaskControlBlock, TaskStatus};
+use switch::__switch;
+
+use crate::config::MAX_SYSCALL_NUM;
+use crate::timer::restart_time_slice;
+use manager::{insert_into_pid2process, tick_task, yield_task};
+
+pub use manager::{add_task, fetch_task, pid2process, remove_from_pid2process};
+pub use process::ProcessControlBlock;
+pub use task::{TaskControlBlock, TaskStatus};
+pub use processor::{
+    current_process, current_task, current_trap_cx, current_trap_cx_user_va, current_user_token,
+    run_tasks, schedule, take_current_task, try_current_task_id,
+};
+
+//暂停当前任务，放回任务管理器的队尾，切换到下一个任务
+pub fn suspend_current_and_run_next() {
+    // There must be an application running.
+    let task = take_current_task().unwrap();
 
-struct TaskManager {
-    num_app: usize,
-    inner: UPSafeCell<TaskManagerInner>,
-} 
+    // ---- access current TCB exclusively
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    // Change status to Ready
+    task_inner.tasks_status = TaskStatus::Ready;
+    task_inner.account_kernel_time();
+    drop(task_inner);
+    // ---- release current PCB
 
-struct TaskManagerInner {
-    tasks: [TaskControlBlock; MAX_APP_NUM],
-    current_task: usize,
+    // push back to ready queue.
+    add_task(task);
+    // jump to scheduling cycle
+    schedule(task_cx_ptr);
 }
 
-impl TaskManager {
-    fn mark_current_suspended(&self) {
-        let mut inner = self.inner.exclusive_access(); //获取可变引用
-        let current = inner.current_task;
-        inner.tasks[current].tasks_status = TaskStatus::Ready;
-    }
+//阻塞当前任务：不放回就绪队列，直到被 wakeup_task 唤醒
+//调用前需要先把当前任务登记到某个等待队列中（例如定时器队列），否则它将永远不会被唤醒
+pub fn block_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    task_inner.tasks_status = TaskStatus::Blocked;
+    task_inner.account_kernel_time();
+    drop(task_inner);
+    drop(task);
+    schedule(task_cx_ptr);
+}
 
-    fn mark_current_exited(&self) {
-        let mut inner = self.inner.exclusive_access();
-        let current = inner.current_task;
-        inner.tasks[current].tasks_status = TaskStatus::Exited;
+//唤醒一个被阻塞的任务，把它放回就绪队列
+//所在进程已经退出的线程不再唤醒
+pub fn wakeup_task(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.is_zombie() {
+        return;
     }
+    task_inner.tasks_status = TaskStatus::Ready;
+    drop(task_inner);
+    add_task(task);
+}
 
-    //寻找一个运行状态为 Ready 的应用并返回其 ID
-    fn find_next_task(&self) -> Option<usize> {
-        let inner = self.inner.exclusive_access(); //可变借用
-        let current = inner.current_task;
-        /*
-        举例：
-        current = 3;
-        num_app = 5;
-        (current + 1..current + self.num_app + 1) 即 [4,9) 即 4, 5, 6, 7, 8
-        对 num_app 取模得到下标 [4 % 5, 5 % 5, 6 % 5, 7 % 5, 8 % 5] = [4, 0, 1, 2, 3]
-        
-        find 方法返回一个 Option<usize> 类型的值：
-        如果找到了满足条件的 id，则返回 Some(id)。如果没有找到任何满足条件的 id，则返回 None。
-        */
-        (current + 1..current + self.num_app + 1)
-            .map(|id| id % self.num_app)
-            .find(|id| {
-                inner.tasks[*id].tasks_status == TaskStatus::Ready
-            })
+//时间片用完：由调度器决定当前任务是否被抢占
+pub fn tick_current_and_maybe_run_next() {
+    let task = current_task().unwrap();
+    let preempt = tick_task(&task);
+    drop(task);
+    if preempt {
+        suspend_current_and_run_next();
+    } else {
+        restart_time_slice();
     }
+}
 
-    //运行下一个任务
-    fn run_next_task(&self) {
-        if let Some(next) = self.find_next_task() {
-            let mut inner = self.inner.exclusive_access(); //获取独占访问权
-            let current = inner.current_task;
-            //更新下一个任务的状态和索引
-            inner.tasks[next].tasks_status = TaskStatus::Running;
-            inner.current_task = next;
-            //获取当前和下一个任务的上下文指针
-            let current_task_cx_ptr = &mut inner.tasks[current].task_cx as *mut TaskContext;
-            let next_task_cx_ptr = &inner.tasks[next].task_cx as *const TaskContext;
-            //提前释放独占访问权，防止后续代码中再借用 inner
-            //需要手动 drop 掉我们获取到的 TaskManagerInner 的来自 UPSafeCell 的借用标记
-            drop(inner); 
-            unsafe {
-                __switch(current_task_cx_ptr, next_task_cx_ptr);
+//当前任务主动让出 CPU ，通知调度器后切换到下一个任务
+pub fn yield_current_and_run_next() {
+    let task = current_task().unwrap();
+    yield_task(&task);
+    drop(task);
+    suspend_current_and_run_next();
+}
+
+//初始进程的 pid ，它退出意味着系统中已经没有需要运行的进程
+pub const INITPROC_PID: usize = 0;
+
+//退出当前线程：它的用户栈和 Trap 上下文立即被回收，内核栈等到 sys_waittid 时回收
+//主线程退出意味着整个进程退出：进程成为僵尸进程，等待父进程回收
+pub fn exit_current_and_run_next(exit_code: i32) {
+    // take from Processor
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let process = task.process.upgrade().unwrap();
+    let tid = task_inner.res.as_ref().unwrap().tid;
+    // record exit code
+    task_inner.exit_code = Some(exit_code);
+    task_inner.tasks_status = TaskStatus::Zombie;
+    task_inner.res = None;
+    //这里还在使用该线程的内核栈，线程控制块不能被回收
+    drop(task_inner);
+    drop(task);
+    //tid 已经被回收，之后可能分配给新的线程
+    process
+        .inner_exclusive_access()
+        .deadlock_detector
+        .clear_thread(tid);
+    if tid == 0 {
+        let pid = process.getpid();
+        if pid == INITPROC_PID {
+            println!(
+                "[kernel] Init process exit with exit_code {} ...",
+                exit_code
+            );
+        }
+        *ALIVE_PROCESSES.exclusive_access() -= 1;
+
+        // **** access current PCB exclusively
+        let mut process_inner = process.inner_exclusive_access();
+        // mark this process as a zombie process
+        process_inner.is_zombie = true;
+        // record exit code of main process
+        process_inner.exit_code = exit_code;
+
+        // ++++++ access initproc PCB exclusively
+        //把所有子进程过继给 initproc ；initproc 自己退出时子进程保留在原处，等它们全部退出后再关机
+        if pid != INITPROC_PID {
+            let mut initproc_inner = INITPROC.inner_exclusive_access();
+            for child in process_inner.children.iter() {
+                child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+                initproc_inner.children.push(child.clone());
             }
-            //跳转到用户态
-        } else {
-            println!("All applications completed!");
-            shutdown(false);
+            process_inner.children.clear();
         }
-    }
+        // ++++++ release parent PCB
 
-    fn run_first_task(&self) -> ! {
-        let mut inner = self.inner.exclusive_access();
-        let task0 = &mut inner.tasks[0];
-        task0.tasks_status = TaskStatus::Running;
-        let next_task_cx_ptr = &task0.task_cx as *const TaskContext;
-        drop(inner);
-        let mut _unused = TaskContext::zero_init();
-        //第一次传入一个未使用的上下文
-        unsafe {
-            __switch(&mut _unused as *mut TaskContext, next_task_cx_ptr);
+        //其余线程也随之退出：回收它们的用户栈和 Trap 上下文
+        //它们可能还在就绪队列或等待队列中，标记为 Zombie 之后不会再被调度
+        let mut recycle_res = Vec::new();
+        for task in process_inner.tasks.iter().flatten() {
+            let mut task_inner = task.inner_exclusive_access();
+            task_inner.tasks_status = TaskStatus::Zombie;
+            if let Some(res) = task_inner.res.take() {
+                recycle_res.push(res);
+            }
         }
-        panic!("unreachable in run_first_task!");
+        //回收 TaskUserRes 时需要访问进程控制块，先释放借用
+        drop(process_inner);
+        recycle_res.clear();
+
+        let mut process_inner = process.inner_exclusive_access();
+        // deallocate user space
+        //提前回收地址空间中的物理页帧，页表所在的页帧等到父进程回收时才被回收
+        process_inner.memory_set.recycle_data_pages();
+        //除了主线程以外的线程控制块都可以回收了，主线程的内核栈还在使用，等到父进程回收进程时才回收
+        process_inner.tasks.truncate(1);
     }
+    drop(process);
+    // we do not have to save task context
+    let mut _unused = TaskContext::zero_init();
+    schedule(&mut _unused as *mut _);
 }
 
 lazy_static! {
-    static ref TASK_MANAGER: TaskManager = {
-        let num_app = get_num_app(); //获取app数量
-        //任务数组，包含app数量个任务控制块，进行初始化
-        let mut tasks = [
-            TaskControlBlock {
-                task_cx: TaskContext::zero_init(), //任务上下文初始化为0
-                tasks_status: TaskStatus::UnInit, //任务状态为未初始化
-            }; 
-            MAX_APP_NUM
-        ];
-        //循环赋值
-        for (i, task) in tasks.iter_mut().enumerate() {
-            task.task_cx = TaskContext::goto_restore(init_app_cx(i)); //任务上下文赋值
-            task.tasks_status = TaskStatus::Ready; //将任务设为准备状态
-        }
-        //创建 TaskManager 实例
-        TaskManager {
-            num_app,
-            inner: unsafe {
-                UPSafeCell::new(TaskManagerInner {
-                    tasks,
-                    current_task: 0,
-                })
-            },
-        }
-    };
+    //初始进程，其他所有进程都是它的后代
+    pub static ref INITPROC: Arc<ProcessControlBlock> =
+        ProcessControlBlock::new(get_app_data_by_name("initproc").unwrap());
 }
 
-pub fn suspend_current_and_run_next() {
-    mark_current_suspended(); //使当前任务暂停
-    run_next_task(); //尝试切换到下一个应用
+lazy_static! {
+    //还没有退出的进程数量
+    static ref ALIVE_PROCESSES: UPSafeCell<usize> = unsafe { UPSafeCell::new(0) };
+}
+
+//新创建了一个进程，登记到 pid 映射中
+pub fn process_created(process: &Arc<ProcessControlBlock>) {
+    *ALIVE_PROCESSES.exclusive_access() += 1;
+    insert_into_pid2process(process.getpid(), process.clone());
+}
+
+//是否所有进程都已退出，只有这时内核才关机
+pub fn all_processes_exited() -> bool {
+    *ALIVE_PROCESSES.exclusive_access() == 0
+}
+
+pub fn add_initproc() {
+    process_created(&INITPROC);
+    let task = INITPROC.inner_exclusive_access().get_task(0);
+    add_task(task);
 }
 
-pub fn exit_current_and_run_next() {
-    mark_current_exited(); //使当前任务退出
-    run_next_task();
+//从用户态陷入内核时调用
+pub fn account_trap_enter() {
+    current_task().unwrap().inner_exclusive_access().account_user_time();
 }
 
-fn mark_current_suspended() {
-    TASK_MANAGER.mark_current_suspended(); //修改当前应用的运行状态
+//返回用户态前调用
+pub fn account_trap_return() {
+    current_task().unwrap().inner_exclusive_access().account_kernel_time();
 }
 
-fn mark_current_exited() {
-    TASK_MANAGER.mark_current_exited();
+//记录当前任务发起了一次系统调用，MAX_SYSCALL_NUM 覆盖了所有已实现的系统调用号，不存在的系统调用号不计数
+pub fn record_syscall(syscall_id: usize) {
+    if syscall_id < MAX_SYSCALL_NUM {
+        current_task().unwrap().inner_exclusive_access().syscall_times[syscall_id] += 1;
+    }
 }
 
-fn run_next_task() {
-    TASK_MANAGER.run_next_task();
+//当前进程在 va 处发生了缺页，能够处理时返回 true ，让应用重新执行这条指令
+pub fn handle_page_fault(va: usize, write: bool) -> bool {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .handle_page_fault(VirtAddr::from(va).floor(), write)
 }
 
-pub fn run_first_task() {
-    TASK_MANAGER.run_first_task();
-}
\ No newline at end of file
+//内核即将读取当前进程地址空间中的 [ptr, ptr + len)
+pub fn prepare_user_read(ptr: usize, len: usize) {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .prepare_access(ptr, len, false);
+}
+
+//内核即将写入当前进程地址空间中的 [ptr, ptr + len)
+pub fn prepare_user_write(ptr: usize, len: usize) {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .prepare_access(ptr, len, true);
+}
+
+//内核即将读取当前进程地址空间中从 ptr 开始的字符串
+pub fn prepare_user_str(ptr: usize) {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .prepare_str(ptr);
+}
+
+//当前进程的 pid
+pub fn current_task_id() -> usize {
+    current_process().getpid()
+}
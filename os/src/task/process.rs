@@ -0,0 +1,205 @@
+use super::id::{pid_alloc, PidHandle, RecycleAllocator};
+use super::TaskControlBlock;
+use crate::config::USER_STACK_BASE;
+use crate::mm::{MemorySet, VirtAddr, KERNEL_SPACE};
+use crate::sync::{Condvar, DeadlockDetector, Mutex, Semaphore, UPSafeCell};
+use crate::trap::{trap_handler, TrapContext};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefMut;
+
+//进程控制块：进程是资源（地址空间等）的容器，其中的线程才是被调度的单位
+pub struct ProcessControlBlock {
+    // immutable
+    pub pid: PidHandle, //进程标识符
+    // mutable
+    inner: UPSafeCell<ProcessControlBlockInner>,
+}
+
+pub struct ProcessControlBlockInner {
+    pub is_zombie: bool, //已退出但还没有被父进程回收
+    pub memory_set: MemorySet, //进程的地址空间
+    pub parent: Option<Weak<ProcessControlBlock>>, //父进程，使用弱引用避免父子之间形成循环引用
+    pub children: Vec<Arc<ProcessControlBlock>>, //子进程
+    pub exit_code: i32, //退出码，在父进程回收它的时候读取
+    pub tasks: Vec<Option<Arc<TaskControlBlock>>>, //进程中的线程，下标为 tid ，被回收的线程为 None
+    pub task_res_allocator: RecycleAllocator, //tid 分配器
+    //进程中线程共享的同步对象，下标就是返回给用户程序的编号
+    pub mutex_list: Vec<Option<Arc<dyn Mutex>>>,
+    pub semaphore_list: Vec<Option<Arc<Semaphore>>>,
+    pub condvar_list: Vec<Option<Arc<Condvar>>>,
+    pub deadlock_detector: DeadlockDetector, //互斥锁和信号量的分配情况，默认不启用死锁检测
+    pub heap_bottom: usize, //堆底，紧接在 ELF 各段上面的保护页之后
+    pub program_brk: usize, //堆顶，堆占用 [heap_bottom, program_brk)
+}
+
+impl ProcessControlBlockInner {
+    //获取进程地址空间的 token
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+    pub fn alloc_tid(&mut self) -> usize {
+        self.task_res_allocator.alloc()
+    }
+    pub fn dealloc_tid(&mut self, tid: usize) {
+        self.task_res_allocator.dealloc(tid)
+    }
+    //还没有退出的线程数，已经退出但还没有被回收的线程不算在内
+    pub fn alive_thread_count(&self) -> usize {
+        self.tasks
+            .iter()
+            .flatten()
+            .filter(|task| task.inner_exclusive_access().res.is_some())
+            .count()
+    }
+    pub fn get_task(&self, tid: usize) -> Arc<TaskControlBlock> {
+        self.tasks[tid].as_ref().unwrap().clone()
+    }
+    //[start, end) 是否和堆所在的页面有交集
+    //sbrk 通过起始地址找到堆所在的逻辑段，所以不允许 munmap/mprotect 把它拆开或者删除
+    pub fn overlaps_heap(&self, start: usize, end: usize) -> bool {
+        let heap_start = VirtAddr::from(self.heap_bottom).floor();
+        let heap_end = VirtAddr::from(self.program_brk).ceil();
+        VirtAddr::from(start).floor() < heap_end && heap_start < VirtAddr::from(end).ceil()
+    }
+    //把堆顶移动 size 字节，返回原来的堆顶
+    //新的堆顶低于堆底、进入用户栈区域或者堆扩展的部分和其他映射重叠时返回 None
+    pub fn change_program_brk(&mut self, size: isize) -> Option<usize> {
+        let old_brk = self.program_brk;
+        let new_brk = (old_brk as isize).checked_add(size)?;
+        if new_brk < self.heap_bottom as isize || new_brk as usize > USER_STACK_BASE {
+            return None;
+        }
+        let new_brk = new_brk as usize;
+        let changed = if size < 0 {
+            self.memory_set
+                .shrink_to(self.heap_bottom.into(), new_brk.into())
+        } else {
+            self.memory_set
+                .append_to(self.heap_bottom.into(), new_brk.into())
+        };
+        if changed {
+            self.program_brk = new_brk;
+            Some(old_brk)
+        } else {
+            None
+        }
+    }
+}
+
+impl ProcessControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, ProcessControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+    //通过应用的 ELF 数据创建一个只有主线程的新进程，目前只有初始进程 initproc 是这样创建的
+    pub fn new(elf_data: &'static [u8]) -> Arc<Self> {
+        // memory_set with elf program headers/trampoline
+        let (memory_set, heap_bottom, entry_point) = MemorySet::from_elf(elf_data);
+        let process = Arc::new(Self {
+            pid: pid_alloc(),
+            inner: unsafe {
+                UPSafeCell::new(ProcessControlBlockInner {
+                    is_zombie: false,
+                    memory_set,
+                    parent: None,
+                    children: Vec::new(),
+                    exit_code: 0,
+                    tasks: Vec::new(),
+                    task_res_allocator: RecycleAllocator::new(),
+                    mutex_list: Vec::new(),
+                    semaphore_list: Vec::new(),
+                    condvar_list: Vec::new(),
+                    deadlock_detector: DeadlockDetector::new(),
+                    heap_bottom,
+                    program_brk: heap_bottom,
+                })
+            },
+        });
+        //创建主线程，同时在地址空间中映射它的用户栈和 Trap 上下文
+        let task = Arc::new(TaskControlBlock::new(Arc::clone(&process), USER_STACK_BASE, true));
+        let task_inner = task.inner_exclusive_access();
+        let trap_cx = task_inner.get_trap_cx();
+        let ustack_top = task_inner.res.as_ref().unwrap().ustack_top();
+        drop(task_inner);
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            ustack_top,
+            KERNEL_SPACE.exclusive_access().token(), //内核地址空间的 token
+            task.kstack.get_top(),
+            trap_handler as usize,
+        );
+        process.inner_exclusive_access().tasks.push(Some(task));
+        process
+    }
+    //用新的 ELF 替换当前进程的地址空间，只允许单线程的进程调用
+    pub fn exec(self: &Arc<Self>, elf_data: &'static [u8]) {
+        assert_eq!(self.inner_exclusive_access().alive_thread_count(), 1);
+        let (memory_set, heap_bottom, entry_point) = MemorySet::from_elf(elf_data);
+        //替换地址空间，原来的地址空间（包括主线程的用户栈和 Trap 上下文以及堆）被回收
+        let mut inner = self.inner_exclusive_access();
+        inner.memory_set = memory_set;
+        inner.heap_bottom = heap_bottom;
+        inner.program_brk = heap_bottom;
+        drop(inner);
+        //在新的地址空间中重新为主线程分配用户栈和 Trap 上下文
+        let task = self.inner_exclusive_access().get_task(0);
+        let mut task_inner = task.inner_exclusive_access();
+        let res = task_inner.res.as_mut().unwrap();
+        res.alloc_user_res();
+        let ustack_top = res.ustack_top();
+        task_inner.trap_cx_ppn = task_inner.res.as_ref().unwrap().trap_cx_ppn();
+        *task_inner.get_trap_cx() = TrapContext::app_init_context(
+            entry_point,
+            ustack_top,
+            KERNEL_SPACE.exclusive_access().token(),
+            task.kstack.get_top(),
+            trap_handler as usize,
+        );
+    }
+    //复制当前进程得到子进程，子进程的地址空间和父进程写时复制地共享页帧，只允许单线程的进程调用
+    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+        let mut parent = self.inner_exclusive_access();
+        assert_eq!(parent.alive_thread_count(), 1);
+        // copy user space(include trap context)
+        let memory_set = MemorySet::from_existed_user(&mut parent.memory_set);
+        let child = Arc::new(Self {
+            pid: pid_alloc(),
+            inner: unsafe {
+                UPSafeCell::new(ProcessControlBlockInner {
+                    is_zombie: false,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    tasks: Vec::new(),
+                    task_res_allocator: RecycleAllocator::new(),
+                    mutex_list: Vec::new(),
+                    semaphore_list: Vec::new(),
+                    condvar_list: Vec::new(),
+                    deadlock_detector: DeadlockDetector::new(),
+                    heap_bottom: parent.heap_bottom,
+                    program_brk: parent.program_brk,
+                })
+            },
+        });
+        // add child
+        parent.children.push(Arc::clone(&child));
+        //子进程的主线程：用户栈和 Trap 上下文已经随地址空间一起复制过来了
+        let parent_task = parent.get_task(0);
+        drop(parent);
+        let parent_task_inner = parent_task.inner_exclusive_access();
+        let ustack_base = parent_task_inner.res.as_ref().unwrap().ustack_base;
+        let task = Arc::new(TaskControlBlock::new(Arc::clone(&child), ustack_base, false));
+        let mut task_inner = task.inner_exclusive_access();
+        task_inner.inherit_sched(&parent_task_inner);
+        // modify kernel_sp in trap_cx
+        task_inner.get_trap_cx().kernel_sp = task.kstack.get_top();
+        drop(task_inner);
+        drop(parent_task_inner);
+        child.inner_exclusive_access().tasks.push(Some(task));
+        child
+    }
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+}
@@ -0,0 +1,156 @@
+use super::__switch;
+#[cfg(feature = "tickless")]
+use super::manager::has_ready_task;
+use super::manager::time_slice_of;
+use super::{all_processes_exited, fetch_task, TaskStatus, INITPROC};
+#[cfg(feature = "tickless")]
+use crate::timer::pause_time_slice;
+use crate::timer::start_time_slice;
+use crate::sbi::shutdown;
+use super::{ProcessControlBlock, TaskContext, TaskControlBlock};
+use crate::sync::UPSafeCell;
+use core::arch::asm;
+use riscv::register::sstatus;
+use crate::trap::TrapContext;
+use alloc::sync::Arc;
+use lazy_static::*;
+
+//处理器管理结构：维护处理器当前正在执行的任务
+pub struct Processor {
+    current: Option<Arc<TaskControlBlock>>, //当前处理器上正在执行的任务
+    idle_task_cx: TaskContext, //当前处理器上的 idle 控制流的任务上下文
+}
+
+impl Processor {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            idle_task_cx: TaskContext::zero_init(),
+        }
+    }
+    fn get_idle_task_cx_ptr(&mut self) -> *mut TaskContext {
+        &mut self.idle_task_cx as *mut _
+    }
+    //取出当前正在执行的任务
+    pub fn take_current(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.current.take()
+    }
+    //返回当前执行的任务的一份拷贝
+    pub fn current(&self) -> Option<Arc<TaskControlBlock>> {
+        self.current.as_ref().map(Arc::clone)
+    }
+}
+
+lazy_static! {
+    pub static ref PROCESSOR: UPSafeCell<Processor> = unsafe { UPSafeCell::new(Processor::new()) };
+}
+
+//idle 控制流：运行在启动时的栈上，不断从任务管理器中取出任务并切换过去
+pub fn run_tasks() {
+    loop {
+        let mut processor = PROCESSOR.exclusive_access();
+        if let Some(task) = fetch_task() {
+            let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
+            // access coming task TCB exclusively
+            let mut task_inner = task.inner_exclusive_access();
+            //所在进程已经退出的线程不再运行，丢弃即可
+            if task_inner.is_zombie() {
+                continue;
+            }
+            let next_task_cx_ptr = &task_inner.task_cx as *const TaskContext;
+            task_inner.tasks_status = TaskStatus::Running;
+            task_inner.account_switch_in();
+            //手动释放借用，__switch 不会返回到这里
+            drop(task_inner);
+            start_time_slice(time_slice_of(&task));
+            //tickless：没有其他任务在等待 CPU 时不需要时间片中断
+            #[cfg(feature = "tickless")]
+            if !has_ready_task() {
+                pause_time_slice();
+            }
+            processor.current = Some(task);
+            drop(processor);
+            unsafe {
+                __switch(idle_task_cx_ptr, next_task_cx_ptr);
+            }
+        } else {
+            drop(processor);
+            idle();
+        }
+    }
+}
+
+//没有就绪的任务：所有进程都已退出时关机，否则开中断并等待，直到时钟中断唤醒睡眠中的任务
+fn idle() {
+    //没有任务在运行，只需要为睡眠中的任务触发时钟中断
+    start_time_slice(None);
+    if all_processes_exited() {
+        let exit_code = INITPROC.inner_exclusive_access().exit_code;
+        println!("[kernel] All processes exited, shutting down ...");
+        shutdown(exit_code != 0);
+    }
+    //内核态下默认关中断，只在 wfi 前后短暂打开，中断由 trap_from_kernel 处理
+    unsafe {
+        sstatus::set_sie();
+        asm!("wfi");
+        sstatus::clear_sie();
+    }
+}
+
+pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
+    PROCESSOR.exclusive_access().take_current()
+}
+
+pub fn current_task() -> Option<Arc<TaskControlBlock>> {
+    PROCESSOR.exclusive_access().current()
+}
+
+//当前线程所属的进程
+pub fn current_process() -> Arc<ProcessControlBlock> {
+    current_task().unwrap().process.upgrade().unwrap()
+}
+
+pub fn current_user_token() -> usize {
+    let process = current_process();
+    let token = process.inner_exclusive_access().get_user_token();
+    token
+}
+
+pub fn current_trap_cx() -> &'static mut TrapContext {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .get_trap_cx()
+}
+
+//当前线程的 Trap 上下文在应用地址空间中的虚拟地址
+pub fn current_trap_cx_user_va() -> usize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .trap_cx_user_va()
+}
+
+//当前进程的 pid ，处理器已经被借用时返回 None（用于出错时打印诊断信息）
+pub fn try_current_task_id() -> Option<usize> {
+    PROCESSOR.try_exclusive_access().and_then(|processor| {
+        processor
+            .current
+            .as_ref()
+            .and_then(|task| task.process.upgrade())
+            .map(|process| process.getpid())
+    })
+}
+
+//换出当前任务，切换到 idle 控制流，由它继续选择下一个任务
+pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
+    let mut processor = PROCESSOR.exclusive_access();
+    let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
+    drop(processor);
+    unsafe {
+        __switch(switched_task_cx_ptr, idle_task_cx_ptr);
+    }
+}
@@ -0,0 +1,34 @@
+use super::Scheduler;
+use crate::task::TaskControlBlock;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+//先来先服务：不因时钟中断抢占，任务一直运行到主动让出、阻塞或退出
+pub struct FifoScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Scheduler for FifoScheduler {
+    fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.ready_queue.pop_front()
+    }
+    fn on_tick(&mut self, _task: &Arc<TaskControlBlock>) -> bool {
+        false
+    }
+    fn on_yield(&mut self, _task: &Arc<TaskControlBlock>) {}
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
+    //不抢占，也就不需要时间片
+    fn time_slice(&self, _task: &Arc<TaskControlBlock>) -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,64 @@
+use super::Scheduler;
+use crate::task::TaskControlBlock;
+use crate::timer::get_mtime;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+//彩票调度：每个任务持有的彩票数等于它的优先级，随机抽出一张彩票决定下一个任务
+pub struct LotteryScheduler {
+    ready_queue: Vec<Arc<TaskControlBlock>>,
+    seed: u64, //xorshift 伪随机数生成器的状态
+}
+
+impl LotteryScheduler {
+    //xorshift64 ，对调度来说足够随机
+    fn rand(&mut self) -> u64 {
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed = x;
+        x
+    }
+}
+
+impl Scheduler for LotteryScheduler {
+    fn new() -> Self {
+        Self {
+            ready_queue: Vec::new(),
+            //种子不能为 0
+            seed: get_mtime() as u64 | 1,
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push(task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        if self.ready_queue.is_empty() {
+            return None;
+        }
+        let total: usize = self
+            .ready_queue
+            .iter()
+            .map(|task| task.inner_exclusive_access().priority)
+            .sum();
+        let mut winner = (self.rand() % total as u64) as usize;
+        let mut idx = 0;
+        for (i, task) in self.ready_queue.iter().enumerate() {
+            let tickets = task.inner_exclusive_access().priority;
+            if winner < tickets {
+                idx = i;
+                break;
+            }
+            winner -= tickets;
+        }
+        Some(self.ready_queue.remove(idx))
+    }
+    fn on_tick(&mut self, _task: &Arc<TaskControlBlock>) -> bool {
+        true
+    }
+    fn on_yield(&mut self, _task: &Arc<TaskControlBlock>) {}
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
+}
@@ -0,0 +1,67 @@
+use super::Scheduler;
+use crate::task::TaskControlBlock;
+use crate::timer::get_time_ms;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+//队列的层数，第 0 层优先级最高
+const MLFQ_LEVELS: usize = 3;
+//每隔这么长时间（ms）把所有任务提升回第 0 层，避免低优先级任务饥饿
+const MLFQ_BOOST_MS: usize = 1000;
+
+//多级反馈队列：新任务从最高层开始，用完时间片就降一层；主动让出的任务保持在原来的层
+//第 level 层的时间片是任务时间片的 2^level 倍，越低的层时间片越长
+pub struct MlfqScheduler {
+    queues: [VecDeque<Arc<TaskControlBlock>>; MLFQ_LEVELS],
+    last_boost: usize, //上次提升的时刻（ms）
+}
+
+impl MlfqScheduler {
+    //把所有就绪任务提升到第 0 层
+    fn boost(&mut self) {
+        for level in 1..MLFQ_LEVELS {
+            while let Some(task) = self.queues[level].pop_front() {
+                task.inner_exclusive_access().mlfq_level = 0;
+                self.queues[0].push_back(task);
+            }
+        }
+    }
+}
+
+impl Scheduler for MlfqScheduler {
+    fn new() -> Self {
+        Self {
+            queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            last_boost: 0,
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        let level = task.inner_exclusive_access().mlfq_level.min(MLFQ_LEVELS - 1);
+        self.queues[level].push_back(task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.queues.iter_mut().find_map(|queue| queue.pop_front())
+    }
+    //时间片用完：降到下一层
+    fn on_tick(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        let now = get_time_ms();
+        if now - self.last_boost >= MLFQ_BOOST_MS {
+            self.last_boost = now;
+            task.inner_exclusive_access().mlfq_level = 0;
+            self.boost();
+            return true;
+        }
+        let mut inner = task.inner_exclusive_access();
+        inner.mlfq_level = (inner.mlfq_level + 1).min(MLFQ_LEVELS - 1);
+        true
+    }
+    fn on_yield(&mut self, _task: &Arc<TaskControlBlock>) {}
+    fn is_empty(&self) -> bool {
+        self.queues.iter().all(|queue| queue.is_empty())
+    }
+    fn time_slice(&self, task: &Arc<TaskControlBlock>) -> Option<usize> {
+        let inner = task.inner_exclusive_access();
+        //每降一级时间片翻倍，用饱和乘法避免溢出
+        Some(inner.time_slice.saturating_mul(1 << inner.mlfq_level))
+    }
+}
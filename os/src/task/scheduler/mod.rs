@@ -0,0 +1,73 @@
+//! 可替换的调度策略
+//!
+//! 任务管理器只负责保存就绪任务，选择下一个任务的策略由 Scheduler 的实现决定。
+//! 编译时通过 cargo feature 选择调度算法，默认使用 stride 调度：
+//! `sched_rr` 时间片轮转，`sched_fifo` 先来先服务（不抢占），
+//! `sched_mlfq` 多级反馈队列，`sched_lottery` 彩票调度。
+//!
+//! 启用 `tickless` feature 后，就绪队列为空（只有当前任务可以运行）时不再触发时间片中断，
+//! 直到有新的任务进入就绪队列。
+
+#[cfg(feature = "sched_fifo")]
+mod fifo;
+#[cfg(feature = "sched_lottery")]
+mod lottery;
+#[cfg(feature = "sched_mlfq")]
+mod mlfq;
+#[cfg(feature = "sched_rr")]
+mod round_robin;
+#[cfg(not(any(
+    feature = "sched_rr",
+    feature = "sched_fifo",
+    feature = "sched_mlfq",
+    feature = "sched_lottery",
+)))]
+mod stride;
+
+use super::TaskControlBlock;
+use alloc::sync::Arc;
+
+pub trait Scheduler {
+    fn new() -> Self;
+    //加入一个就绪任务
+    fn add(&mut self, task: Arc<TaskControlBlock>);
+    //选出下一个要运行的任务
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>>;
+    //正在运行的任务用完了时间片，返回是否应该抢占它；不抢占时它会开始一个新的时间片
+    fn on_tick(&mut self, task: &Arc<TaskControlBlock>) -> bool;
+    //正在运行的任务主动让出 CPU
+    fn on_yield(&mut self, task: &Arc<TaskControlBlock>);
+    //就绪队列是否为空
+    fn is_empty(&self) -> bool;
+    //task 被调度后可以运行的时间片长度（ms），None 表示不因时间片用完而抢占
+    //默认使用任务自己的时间片长度
+    fn time_slice(&self, task: &Arc<TaskControlBlock>) -> Option<usize> {
+        Some(task.inner_exclusive_access().time_slice)
+    }
+}
+
+#[cfg(any(
+    all(feature = "sched_rr", feature = "sched_fifo"),
+    all(feature = "sched_rr", feature = "sched_mlfq"),
+    all(feature = "sched_rr", feature = "sched_lottery"),
+    all(feature = "sched_fifo", feature = "sched_mlfq"),
+    all(feature = "sched_fifo", feature = "sched_lottery"),
+    all(feature = "sched_mlfq", feature = "sched_lottery"),
+))]
+compile_error!("at most one of the sched_* features can be enabled");
+
+#[cfg(feature = "sched_rr")]
+pub type SchedulerImpl = round_robin::RoundRobinScheduler;
+#[cfg(feature = "sched_fifo")]
+pub type SchedulerImpl = fifo::FifoScheduler;
+#[cfg(feature = "sched_mlfq")]
+pub type SchedulerImpl = mlfq::MlfqScheduler;
+#[cfg(feature = "sched_lottery")]
+pub type SchedulerImpl = lottery::LotteryScheduler;
+#[cfg(not(any(
+    feature = "sched_rr",
+    feature = "sched_fifo",
+    feature = "sched_mlfq",
+    feature = "sched_lottery",
+)))]
+pub type SchedulerImpl = stride::StrideScheduler;
@@ -0,0 +1,42 @@
+use super::Scheduler;
+use crate::task::TaskControlBlock;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+//stride 调度：每次选出行程值最小的任务，被选中后行程值增加一个步长 BIG_STRIDE / priority
+pub struct StrideScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Scheduler for StrideScheduler {
+    fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    //取出行程值最小的任务，并为它的行程值加上一个步长
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        //行程值可能溢出回绕，比较时看两者之差的符号而不是直接比较大小
+        //因为优先级不小于 2 ，任意两个就绪任务的行程值之差不超过 BIG_STRIDE / 2 ，这样比较是正确的
+        let (idx, _) = self.ready_queue.iter().enumerate().min_by(|(_, a), (_, b)| {
+            let a_pass = a.inner_exclusive_access().pass;
+            let b_pass = b.inner_exclusive_access().pass;
+            (a_pass.wrapping_sub(b_pass) as isize).cmp(&0)
+        })?;
+        let task = self.ready_queue.remove(idx).unwrap();
+        let mut inner = task.inner_exclusive_access();
+        inner.pass = inner.pass.wrapping_add(inner.stride());
+        drop(inner);
+        Some(task)
+    }
+    fn on_tick(&mut self, _task: &Arc<TaskControlBlock>) -> bool {
+        true
+    }
+    fn on_yield(&mut self, _task: &Arc<TaskControlBlock>) {}
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
+}
@@ -1,15 +1,124 @@
-use super::TaskContext;
+use super::id::{kstack_alloc, KernelStack, TaskUserRes};
+use super::{ProcessControlBlock, TaskContext};
+use crate::config::{BIG_STRIDE, DEFAULT_PRIORITY, DEFAULT_TIME_SLICE, MAX_SYSCALL_NUM};
+use crate::mm::PhysPageNum;
+use crate::sync::UPSafeCell;
+use crate::timer::get_mtime;
+use crate::trap::TrapContext;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefMut;
 
-#[derive(Clone, Copy, PartialEq)]
+//取值会通过 sys_task_info 交给用户程序，需要和用户库中的定义保持一致
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(usize)]
 pub enum TaskStatus {
-    UnInit, // 未初始化
     Ready, // 准备运行
     Running, // 正在运行
-    Exited, // 已退出
+    Blocked, // 正在等待某个事件（例如睡眠到期），不在就绪队列中
+    Zombie, // 已退出但还没有被回收
 }
 
-#[derive(Clone, Copy)]
+//线程控制块：线程是被调度的基本单位，同一进程的所有线程共享进程的地址空间
 pub struct TaskControlBlock {
-    pub tasks_status: TaskStatus,
+    // immutable
+    pub process: Weak<ProcessControlBlock>, //所属的进程
+    pub kstack: KernelStack, //内核栈
+    // mutable
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+pub struct TaskControlBlockInner {
+    pub res: Option<TaskUserRes>, //tid 、用户栈和 Trap 上下文，线程退出时被回收
+    pub trap_cx_ppn: PhysPageNum, //Trap 上下文被实际存放在物理页帧的物理页号
     pub task_cx: TaskContext,
-}
\ No newline at end of file
+    pub tasks_status: TaskStatus,
+    pub exit_code: Option<i32>, //退出码，线程退出前为 None
+    pub priority: usize, //stride 调度的优先级，不小于 2
+    pub pass: usize, //stride 调度中累计的行程值，每次被调度时增加一个步长
+    pub mlfq_level: usize, //多级反馈队列调度中所在的层
+    pub time_slice: usize, //每次被调度后可以运行的时间片长度（ms）
+    pub first_time: Option<usize>, //第一次被调度的时刻（mtime），还没被调度过时为 None
+    pub user_time: usize, //在用户态累计运行的时间（mtime 增量）
+    pub kernel_time: usize, //在内核态累计运行的时间（mtime 增量）
+    stamp: usize, //上一次切换用户态/内核态或被调度的时刻，用于计算上面两个时间
+    pub syscall_times: Vec<u32>, //每个系统调用被调用的次数，长度为 MAX_SYSCALL_NUM ，放在堆上以免占用内核栈
+}
+
+impl TaskControlBlockInner {
+    //获取 Trap 上下文的可变引用
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+    pub fn is_zombie(&self) -> bool {
+        self.tasks_status == TaskStatus::Zombie
+    }
+    //从用户态陷入内核，结束一段用户态时间
+    pub fn account_user_time(&mut self) {
+        let now = get_mtime();
+        self.user_time += now - self.stamp;
+        self.stamp = now;
+    }
+    //返回用户态或被换出，结束一段内核态时间
+    pub fn account_kernel_time(&mut self) {
+        let now = get_mtime();
+        self.kernel_time += now - self.stamp;
+        self.stamp = now;
+    }
+    //被调度到处理器上，从这时开始重新计时
+    pub fn account_switch_in(&mut self) {
+        let now = get_mtime();
+        self.first_time.get_or_insert(now);
+        self.stamp = now;
+    }
+    //步长与优先级成反比，优先级越高被调度得越频繁
+    pub fn stride(&self) -> usize {
+        BIG_STRIDE / self.priority
+    }
+    //新线程（或子进程的主线程）从创建它的线程继承调度参数，并从它当前的行程值开始，避免刚创建就长期占用 CPU
+    pub fn inherit_sched(&mut self, creator: &TaskControlBlockInner) {
+        self.priority = creator.priority;
+        self.pass = creator.pass;
+        self.time_slice = creator.time_slice;
+    }
+}
+
+impl TaskControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+    //在进程中创建一个线程，alloc_user_res 为 false 时用户栈和 Trap 上下文已经存在于地址空间中（fork 的情况）
+    pub fn new(
+        process: Arc<ProcessControlBlock>,
+        ustack_base: usize,
+        alloc_user_res: bool,
+    ) -> Self {
+        let res = TaskUserRes::new(Arc::clone(&process), ustack_base, alloc_user_res);
+        let trap_cx_ppn = res.trap_cx_ppn();
+        let kstack = kstack_alloc();
+        let kstack_top = kstack.get_top();
+        Self {
+            process: Arc::downgrade(&process),
+            kstack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    res: Some(res),
+                    trap_cx_ppn,
+                    task_cx: TaskContext::goto_trap_return(kstack_top), //第一次被调度时从 trap_return 开始执行
+                    tasks_status: TaskStatus::Ready,
+                    exit_code: None,
+                    priority: DEFAULT_PRIORITY,
+                    pass: 0,
+                    mlfq_level: 0,
+                    time_slice: DEFAULT_TIME_SLICE,
+                    first_time: None,
+                    user_time: 0,
+                    kernel_time: 0,
+                    stamp: 0,
+                    syscall_times: vec![0; MAX_SYSCALL_NUM],
+                })
+            },
+        }
+    }
+}
@@ -2,26 +2,173 @@
 
 use riscv::register::time;
 use crate::sbi::set_mtimecmp;
-use crate::config::CLOCK_FREQ;
+use crate::config::{CLOCK_FREQ, TTY_POLL_INTERVAL};
+use crate::sync::UPSafeCell;
+use crate::task::{wakeup_task, TaskControlBlock};
+use crate::tty::TTY;
+use alloc::collections::BinaryHeap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use lazy_static::*;
 
-const TICKS_PER_SEC: usize = 100;
 const MICRO_PER_SEC: usize = 1000;
+const USEC_PER_SEC: usize = 1_000_000;
 
 // 获取当前 mtime 计数器的值。作用 :用来统计处理器自上电以来经过了多少个内置时钟的时钟周期
 pub fn get_mtime() -> usize {
     time::read()
 }
 
-//CLOCK_FREQ / TICKS_PER_SEC 是10ms之内计数器的增量
-//CLOCK_FREQ是平台的时钟频率（hz），也是在1s内计数器增加的增量。1s=1000ms。
-//想增加10ms 就要将CLOCK_FREQ/100即可
-//10ms 之后一个 S 特权级时钟中断就会被触发。
+//当前任务的时间片
+struct TimeSlice {
+    len: Option<usize>, //时间片长度（mtime 增量），None 表示当前任务不会因为时间片用完而被抢占
+    end: Option<usize>, //时间片结束的时刻，None 表示没有在计时
+}
+
+lazy_static! {
+    static ref TIME_SLICE: UPSafeCell<TimeSlice> =
+        unsafe { UPSafeCell::new(TimeSlice { len: None, end: None }) };
+}
+
+//在当前时间片结束、最早的睡眠截止时间和下一次检查控制台输入中最早的时刻触发下一次时钟中断
+//三者都没有时不再触发时钟中断
 pub fn set_next_trigger() {
-    set_mtimecmp(get_mtime() + CLOCK_FREQ / TICKS_PER_SEC);
+    let slice_end = TIME_SLICE.exclusive_access().end;
+    //有任务阻塞在 sys_read 中时，需要定期触发时钟中断检查输入
+    let tty_poll = if TTY.exclusive_access().has_readers() {
+        Some(get_mtime() + ms_to_mtime(TTY_POLL_INTERVAL))
+    } else {
+        None
+    };
+    let next = [slice_end, next_deadline(), tty_poll]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(usize::MAX);
+    set_mtimecmp(next);
+}
+
+//任务被调度到处理器上时开始一个 ms 毫秒的时间片，ms 为 None 时不计时
+pub fn start_time_slice(ms: Option<usize>) {
+    let len = ms.map(ms_to_mtime);
+    *TIME_SLICE.exclusive_access() = TimeSlice {
+        len,
+        end: len.map(|len| get_mtime().saturating_add(len)),
+    };
+    set_next_trigger();
+}
+
+//时间片用完但当前任务没有被抢占，按相同的长度开始下一个时间片
+pub fn restart_time_slice() {
+    let mut slice = TIME_SLICE.exclusive_access();
+    slice.end = slice.len.map(|len| get_mtime().saturating_add(len));
+    drop(slice);
+    set_next_trigger();
+}
+
+//暂停计时但记住时间片长度：tickless 模式下只有当前任务可以运行时不需要时间片中断
+#[cfg(feature = "tickless")]
+pub fn pause_time_slice() {
+    TIME_SLICE.exclusive_access().end = None;
+    set_next_trigger();
+}
+
+//恢复被暂停的计时：tickless 模式下有其他任务进入就绪队列时使用
+#[cfg(feature = "tickless")]
+pub fn resume_time_slice() {
+    let mut slice = TIME_SLICE.exclusive_access();
+    if slice.end.is_some() {
+        return;
+    }
+    slice.end = slice.len.map(|len| get_mtime().saturating_add(len));
+    drop(slice);
+    set_next_trigger();
+}
+
+//当前时间片是否已经用完
+pub fn time_slice_expired() -> bool {
+    TIME_SLICE
+        .exclusive_access()
+        .end
+        .map_or(false, |end| get_mtime() >= end)
 }
 
 //以ms为单位返回当前计数器的值。
 //CLOCK_FREQ / MICRO_PER_SEC 即1ms
 pub fn get_time_ms() -> usize {
     get_mtime() / (CLOCK_FREQ / MICRO_PER_SEC)
-}
\ No newline at end of file
+}
+
+//把计数器的增量换算成毫秒数
+pub fn mtime_to_ms(mtime: usize) -> usize {
+    mtime / (CLOCK_FREQ / MICRO_PER_SEC)
+}
+
+//把毫秒数换算成计数器的增量，溢出时取 usize::MAX
+pub fn ms_to_mtime(ms: usize) -> usize {
+    ms.saturating_mul(CLOCK_FREQ / MICRO_PER_SEC)
+}
+
+//把微秒数换算成计数器的增量，先乘后除避免 CLOCK_FREQ 不能被 10^6 整除时丢失精度
+pub fn us_to_mtime(us: usize) -> usize {
+    us * CLOCK_FREQ / USEC_PER_SEC
+}
+
+//一个睡眠中的任务：等到 mtime 达到 expire 时被唤醒
+pub struct TimerCondVar {
+    pub expire: usize,
+    pub task: Arc<TaskControlBlock>,
+}
+
+impl PartialEq for TimerCondVar {
+    fn eq(&self, other: &Self) -> bool {
+        self.expire == other.expire
+    }
+}
+impl Eq for TimerCondVar {}
+//BinaryHeap 是大根堆，这里把比较反过来，使截止时间最早的在堆顶
+impl PartialOrd for TimerCondVar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for TimerCondVar {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.expire.cmp(&self.expire)
+    }
+}
+
+lazy_static! {
+    //按截止时间排序的定时器队列
+    static ref TIMERS: UPSafeCell<BinaryHeap<TimerCondVar>> =
+        unsafe { UPSafeCell::new(BinaryHeap::<TimerCondVar>::new()) };
+}
+
+//让 task 在 mtime 达到 expire 时被唤醒
+pub fn add_timer(expire: usize, task: Arc<TaskControlBlock>) {
+    TIMERS.exclusive_access().push(TimerCondVar { expire, task });
+}
+
+//最早的截止时间，没有睡眠中的任务时返回 None
+fn next_deadline() -> Option<usize> {
+    TIMERS.exclusive_access().peek().map(|timer| timer.expire)
+}
+
+//唤醒所有截止时间已到的任务
+pub fn check_timer() {
+    let current = get_mtime();
+    let mut timers = TIMERS.exclusive_access();
+    let mut expired = Vec::new();
+    while let Some(timer) = timers.peek() {
+        if timer.expire > current {
+            break;
+        }
+        expired.push(timers.pop().unwrap().task);
+    }
+    //唤醒任务时可能会重新设置时钟中断，需要先释放定时器队列
+    drop(timers);
+    for task in expired {
+        wakeup_task(task);
+    }
+}
@@ -9,23 +9,50 @@ pub struct TrapContext {
     pub sstatus: Sstatus,
     /// CSR sepc
     pub sepc: usize,
+    /// 内核地址空间的 token ，即内核页表的起始物理地址
+    pub kernel_satp: usize,
+    /// 当前应用在内核地址空间中的内核栈栈顶的虚拟地址
+    pub kernel_sp: usize,
+    /// 内核中 trap handler 入口点的虚拟地址
+    pub trap_handler: usize,
+}
+
+///内核中发生 Trap 时保存在当前内核栈上的上下文
+///只保存通用寄存器和 sstatus/sepc ，不涉及地址空间的切换
+#[repr(C)]
+pub struct KernelTrapContext {
+    /// general regs[0..31]
+    pub x: [usize; 32],
+    /// CSR sstatus
+    pub sstatus: Sstatus,
+    /// CSR sepc
+    pub sepc: usize,
 }
 
 impl TrapContext {
-    //设置x2 
+    //设置x2
     pub fn set_sp(&mut self, sp: usize) {
         self.x[2] = sp;
     }
     //初始化context
-    pub fn app_init_context(entry: usize, sp: usize) -> Self {
+    pub fn app_init_context(
+        entry: usize,
+        sp: usize,
+        kernel_satp: usize,
+        kernel_sp: usize,
+        trap_handler: usize,
+    ) -> Self {
         let mut sstatus = sstatus::read();
         sstatus.set_spp(SPP::User);
         let mut cx = Self {
             x: [0; 32],
             sstatus,
-            sepc: entry, //0x80400000 sret后跳到这个地开始执行
+            sepc: entry, //应用的入口地址 sret后跳到这个地开始执行
+            kernel_satp,
+            kernel_sp,
+            trap_handler,
         };
         cx.set_sp(sp); //设置x2为用户栈栈顶
         cx
     }
-}
\ No newline at end of file
+}
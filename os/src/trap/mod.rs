@@ -1,6 +1,6 @@
 mod context;
 
-use core::arch::global_asm;
+use core::arch::{asm, global_asm};
 
 use riscv::register::{
     stvec, stval, sie,
@@ -10,16 +10,37 @@ use riscv::register::{
 
 //use crate::batch::run_next_app;
 use crate::syscall::syscall;
-use crate::task::{exit_current_and_run_next, suspend_current_and_run_next};
-use crate::timer::set_next_trigger;
+use crate::config::TRAMPOLINE;
+use crate::sbi::shutdown;
+use crate::task::{
+    account_trap_enter, account_trap_return, current_task_id, current_trap_cx,
+    current_trap_cx_user_va, current_user_token, exit_current_and_run_next, handle_page_fault,
+    tick_current_and_maybe_run_next, try_current_task_id,
+};
+use crate::timer::{check_timer, set_next_trigger, time_slice_expired};
+use crate::tty::check_input;
+use context::KernelTrapContext;
 pub use context::TrapContext;
 
 global_asm!(include_str!("trap.S"));
 
 pub fn init() {
-    extern "C" { fn __alltraps(); }
+    set_kernel_trap_entry();
+}
+
+//在内核中发生的 Trap 交给 __trap_from_kernel 处理
+fn set_kernel_trap_entry() {
+    extern "C" { fn __trap_from_kernel(); }
+    unsafe {
+        stvec::write(__trap_from_kernel as usize, TrapMode::Direct);
+    }
+}
+
+//回到用户态前才把 stvec 设置为 TRAMPOLINE
+//__alltraps 位于跳板页面，在任何地址空间中都映射在 TRAMPOLINE
+fn set_user_trap_entry() {
     unsafe {
-        stvec::write(__alltraps as usize, TrapMode::Direct);
+        stvec::write(TRAMPOLINE, TrapMode::Direct);
     }
 }
 
@@ -32,8 +53,11 @@ pub fn enable_timer_interrupt() {
 }
 
 #[no_mangle]
-pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
-    //cx 是 a0 规范
+pub fn trap_handler() -> ! {
+    set_kernel_trap_entry(); //接下来在内核中发生的 Trap 不能再走 __alltraps
+    account_trap_enter();
+    //Trap 上下文不在内核栈上了，而是保存在应用地址空间中当前线程的 Trap 上下文页面中
+    let cx = current_trap_cx();
     let scause = scause::read(); //描述Trap的原因
     let stval = stval::read(); //给出Trap附加信息
     //scause 寄存器所保存的 Trap 的原因进行分发处理
@@ -41,24 +65,70 @@ pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
         // U 特权级的 Environment Call（系统调用）
         Trap::Exception(Exception::UserEnvCall) => {
             cx.sepc += 4; //sepc保存的app ecall 地址，+4字节。sret返回时让它在 ecall 下一条指令开始执行
-            // 返回值              syscall ID  参数a0     a1        a2     //内核栈a0变化
-            cx.x[10] = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]) as usize;
+            //           syscall ID  参数a0     a1        a2
+            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
+            //系统调用过程中可能发生任务切换，重新获取 Trap 上下文再写入返回值
+            let cx = current_trap_cx();
+            cx.x[10] = result as usize;
         }
-        //分别处理应用程序出现访存错误和非法指令错误的情形
-        Trap::Exception(Exception::StoreFault) | Trap::Exception(Exception::StoreGuestPageFault) => {
-            println!("[kernel] PageFault in application, kernel killed it.");
-            //run_next_app();//切换并运行下一个应用程序
-            exit_current_and_run_next(); //退出当前任务，运行下一个任务
+        //应用程序出现访存错误：stval 给出出错的虚拟地址
+        Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::StoreGuestPageFault)
+        | Trap::Exception(Exception::StoreMisaligned)
+        | Trap::Exception(Exception::LoadFault)
+        | Trap::Exception(Exception::LoadPageFault)
+        | Trap::Exception(Exception::InstructionFault)
+        | Trap::Exception(Exception::InstructionPageFault)
+        | Trap::Exception(Exception::InstructionMisaligned) => {
+            //按需分配的页面被第一次访问，或者写时复制的页面被第一次写入，处理之后回到用户态重新执行这条指令
+            let handled = match scause.cause() {
+                Trap::Exception(Exception::StorePageFault) => handle_page_fault(stval, true),
+                Trap::Exception(Exception::LoadPageFault)
+                | Trap::Exception(Exception::InstructionPageFault) => handle_page_fault(stval, false),
+                _ => false,
+            };
+            if !handled {
+                println!(
+                    "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, task {} killed by kernel.",
+                    scause.cause(),
+                    stval,
+                    cx.sepc,
+                    current_task_id()
+                );
+                exit_current_and_run_next(-2); //只退出当前任务，运行下一个任务
+            }
         }
-        Trap::Exception(Exception::IllegalInstruction) => {
-            println!("[kernel] IllegalInstruction in application, kernel killed it.");
-            //run_next_app();
-            exit_current_and_run_next(); //退出当前任务，运行下一个任务
+        //应用程序出现非法指令或断点：stval 给出出错的指令编码（可能为0）
+        Trap::Exception(Exception::IllegalInstruction) | Trap::Exception(Exception::Breakpoint) => {
+            println!(
+                "[kernel] {:?} in application, stval = {:#x}, sepc = {:#x}, task {} killed by kernel.",
+                scause.cause(),
+                stval,
+                cx.sepc,
+                current_task_id()
+            );
+            exit_current_and_run_next(-3); //只退出当前任务，运行下一个任务
+        }
+        //其他来自用户态的异常同样只杀死当前任务，不让整个内核 panic
+        Trap::Exception(_) => {
+            println!(
+                "[kernel] Unsupported exception {:?} in application, stval = {:#x}, sepc = {:#x}, task {} killed by kernel.",
+                scause.cause(),
+                stval,
+                cx.sepc,
+                current_task_id()
+            );
+            exit_current_and_run_next(-4);
         }
         //处理触发了一个 S 特权级时钟中断情况
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
-            set_next_trigger(); //重新设置一个 10ms 的计时器
-            suspend_current_and_run_next(); //暂停当前应用并切换到下一个
+            check_timer(); //唤醒截止时间已到的睡眠任务
+            check_input(); //唤醒等待控制台输入的任务
+            if time_slice_expired() {
+                tick_current_and_maybe_run_next(); //由调度器决定是否暂停当前应用并切换到下一个
+            }
+            set_next_trigger(); //重新设置下一次时钟中断
         }
         //遇到目前还不支持的 Trap 类型
         _ => {
@@ -69,5 +139,72 @@ pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
             );
         }
     }
-    cx
-}
\ No newline at end of file
+    trap_return();
+}
+
+//回到用户态：切换到应用地址空间，从 Trap 上下文恢复寄存器并 sret
+#[no_mangle]
+pub fn trap_return() -> ! {
+    account_trap_return();
+    set_user_trap_entry();
+    let trap_cx_ptr = current_trap_cx_user_va(); //当前线程的 Trap 上下文在应用地址空间中的虚拟地址
+    let user_satp = current_user_token(); //应用地址空间的 token
+    extern "C" {
+        fn __alltraps();
+        fn __restore();
+    }
+    //__restore 在跳板页面中的虚拟地址
+    let restore_va = __restore as usize - __alltraps as usize + TRAMPOLINE;
+    unsafe {
+        asm!(
+            "fence.i",
+            "jr {restore_va}",
+            restore_va = in(reg) restore_va,
+            in("a0") trap_cx_ptr,
+            in("a1") user_satp,
+            options(noreturn)
+        );
+    }
+}
+
+//RISC-V 通用寄存器的 ABI 名称，用于打印寄存器
+const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+//内核中发生的 Trap ：时钟中断只可能在 idle 控制流开中断等待时到来，处理后返回继续执行
+//其余的 Trap 说明内核本身出了问题，打印完整的现场后关机
+#[no_mangle]
+fn trap_from_kernel(cx: &mut KernelTrapContext) {
+    let scause = scause::read();
+    let stval = stval::read();
+    if let Trap::Interrupt(Interrupt::SupervisorTimer) = scause.cause() {
+        check_timer();
+        check_input();
+        set_next_trigger();
+        return;
+    }
+    println!(
+        "[kernel] Trap from kernel: {:?}, scause = {:#x}, stval = {:#x}, sepc = {:#x}",
+        scause.cause(),
+        scause.bits(),
+        stval,
+        cx.sepc
+    );
+    match try_current_task_id() {
+        Some(id) => println!("[kernel] current task: {}", id),
+        None => println!("[kernel] current task: unknown"),
+    }
+    for (i, reg) in cx.x.iter().enumerate() {
+        print!("{:>4} = {:#018x}", REG_NAMES[i], reg);
+        if i % 4 == 3 {
+            println!("");
+        } else {
+            print!("  ");
+        }
+    }
+    shutdown(true);
+}
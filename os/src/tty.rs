@@ -0,0 +1,159 @@
+//! 控制台输入的行规程（line discipline）
+//!
+//! 规范模式（canonical）下按行缓冲：支持回显和退格，直到收到回车/换行才把整行交给 sys_read ；
+//! 原始模式（raw）下每收到一个字符就可以被读取。
+
+use crate::sbi::console_getchar;
+use crate::sync::UPSafeCell;
+use crate::task::{wakeup_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+//模式标志位，取值与 Linux termios 的 c_lflag 相同
+pub const TTY_ICANON: usize = 0o2; //规范模式
+pub const TTY_ECHO: usize = 0o10; //回显输入的字符
+
+const LF: u8 = 0x0a;
+const CR: u8 = 0x0d;
+const BS: u8 = 0x08;
+const DL: u8 = 0x7f;
+
+//规范模式下一行的最大长度（包括结尾的换行符）
+const LINE_MAX: usize = 256;
+
+pub struct Tty {
+    flags: usize, //当前模式
+    line: Vec<u8>, //规范模式下正在编辑的一行
+    ready: VecDeque<u8>, //已经可以被 sys_read 读走的字节
+    readers: VecDeque<Arc<TaskControlBlock>>, //因为没有输入而阻塞在 sys_read 中的任务
+}
+
+impl Tty {
+    pub fn new() -> Self {
+        Self {
+            flags: TTY_ICANON | TTY_ECHO,
+            line: Vec::new(),
+            ready: VecDeque::new(),
+            readers: VecDeque::new(),
+        }
+    }
+
+    pub fn flags(&self) -> usize {
+        self.flags
+    }
+
+    //切换模式，包含未知标志位时返回 false
+    pub fn set_flags(&mut self, flags: usize) -> bool {
+        if flags & !(TTY_ICANON | TTY_ECHO) != 0 {
+            return false;
+        }
+        //离开规范模式时，已经输入但还没有回车的内容直接变为可读
+        if flags & TTY_ICANON == 0 {
+            self.ready.extend(self.line.drain(..));
+        }
+        self.flags = flags;
+        true
+    }
+
+    fn echo(&self, c: u8) {
+        if self.flags & TTY_ECHO != 0 {
+            print!("{}", c as char);
+        }
+    }
+
+    //处理输入的一个字符
+    fn input(&mut self, c: u8) {
+        if self.flags & TTY_ICANON == 0 {
+            self.echo(c);
+            self.ready.push_back(c);
+            return;
+        }
+        match c {
+            //回车或换行：整行（以 \n 结尾）变为可读
+            CR | LF => {
+                self.echo(LF);
+                self.line.push(LF);
+                self.ready.extend(self.line.drain(..));
+            }
+            //退格：删除正在编辑的行中最后一个字符，并在终端上擦掉它
+            BS | DL => {
+                if self.line.pop().is_some() && self.flags & TTY_ECHO != 0 {
+                    print!("{} {}", BS as char, BS as char);
+                }
+            }
+            _ => {
+                if self.line.len() < LINE_MAX - 1 {
+                    self.echo(c);
+                    self.line.push(c);
+                }
+            }
+        }
+    }
+
+    //通过 SBI 取出目前所有已经到达的字符
+    pub fn poll(&mut self) {
+        loop {
+            let c = console_getchar();
+            //没有输入时 SBI 返回 -1
+            if c == 0 || c == usize::MAX {
+                break;
+            }
+            self.input(c as u8);
+        }
+    }
+
+    pub fn readable(&self) -> bool {
+        !self.ready.is_empty()
+    }
+
+    //登记一个等待输入的任务，有数据可读时由 check_input 唤醒
+    pub fn add_reader(&mut self, task: Arc<TaskControlBlock>) {
+        self.readers.push_back(task);
+    }
+
+    pub fn has_readers(&self) -> bool {
+        !self.readers.is_empty()
+    }
+
+    //最多读出 len 个字节；规范模式下一次最多读到行尾
+    pub fn read(&mut self, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        while data.len() < len {
+            match self.ready.pop_front() {
+                Some(c) => {
+                    data.push(c);
+                    if c == LF && self.flags & TTY_ICANON != 0 {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        data
+    }
+}
+
+lazy_static! {
+    pub static ref TTY: UPSafeCell<Tty> = unsafe { UPSafeCell::new(Tty::new()) };
+}
+
+//在时钟中断中调用：有任务在等待输入时取出已经到达的字符，有数据可读就唤醒所有等待的任务
+//SBI 控制台没有输入中断，只能这样定期检查
+pub fn check_input() {
+    let mut tty = TTY.exclusive_access();
+    if !tty.has_readers() {
+        return;
+    }
+    tty.poll();
+    if !tty.readable() {
+        return;
+    }
+    let readers: Vec<_> = tty.readers.drain(..).collect();
+    //唤醒任务时可能会重新设置时钟中断，需要先释放 TTY
+    drop(tty);
+    for task in readers {
+        wakeup_task(task);
+    }
+}
@@ -4,15 +4,17 @@
 #[macro_use]
 extern crate user_lib;
 
-use user_lib::{get_time, yield_};
+use user_lib::{get_time, nanosleep, sleep, TimeVal};
 
 #[no_mangle]
 fn main() -> i32 {
-    let current_timer = get_time();
-    let wait_for = current_timer + 3000;
-    while get_time() < wait_for {
-        yield_();
-    }
+    let start = get_time();
+    sleep(3000);
+    //被唤醒的时刻不会早于截止时间
+    assert!(get_time() >= start + 3000);
+    let start = get_time();
+    nanosleep(&TimeVal { sec: 0, usec: 500_000 });
+    assert!(get_time() >= start + 500);
     println!("Test sleep OK!");
     0
-}
\ No newline at end of file
+}
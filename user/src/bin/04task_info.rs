@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, getpid, sleep, task_info, TaskInfo, TaskStatus};
+
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_TASK_INFO: usize = 410;
+
+#[no_mangle]
+fn main() -> i32 {
+    get_time();
+    sleep(100);
+    println!("task_info test");
+    let pid = getpid() as usize;
+    let mut info = TaskInfo::new();
+    assert_eq!(task_info(pid, &mut info), 0);
+    assert_eq!(info.status, TaskStatus::Running);
+    assert_eq!(info.syscall_times[SYSCALL_GET_TIME], 1);
+    assert_eq!(info.syscall_times[SYSCALL_SLEEP], 1);
+    assert!(info.syscall_times[SYSCALL_WRITE] >= 1);
+    assert_eq!(info.syscall_times[SYSCALL_GETPID], 1);
+    assert_eq!(info.syscall_times[SYSCALL_TASK_INFO], 1);
+    //睡眠的 100ms 也计入从第一次被调度开始经过的时间
+    assert!(info.time >= 100);
+    println!(
+        "time = {}ms, user = {}ms, kernel = {}ms",
+        info.time, info.user_time, info.kernel_time
+    );
+    //不存在的任务
+    assert_eq!(task_info(usize::MAX, &mut info), -1);
+    println!("Test task_info OK!");
+    0
+}
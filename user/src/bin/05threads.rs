@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, gettid, thread};
+
+fn worker(arg: usize) -> ! {
+    for _ in 0..100 {
+        print!("{}", arg);
+    }
+    println!("");
+    exit(gettid() as i32)
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let handles = [
+        thread::spawn(worker, 1),
+        thread::spawn(worker, 2),
+        thread::spawn(worker, 3),
+    ];
+    for handle in handles {
+        let tid = handle.tid();
+        //线程以自己的 tid 作为退出码
+        assert_eq!(handle.join(), tid as i32);
+    }
+    println!("main thread exited.");
+    println!("Test threads OK!");
+    0
+}
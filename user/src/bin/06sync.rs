@@ -0,0 +1,99 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait, exit, mutex_blocking_create, mutex_create,
+    mutex_lock, mutex_unlock, semaphore_create, semaphore_down, semaphore_up, thread, yield_,
+};
+
+const THREAD_COUNT: usize = 4;
+const PER_THREAD: usize = 100;
+
+static mut COUNTER: usize = 0;
+static mut READY: bool = false;
+
+//在临界区里读改写之间主动让出 CPU ，没有互斥时一定会丢失更新
+fn add(mutex_id: usize) -> ! {
+    for _ in 0..PER_THREAD {
+        mutex_lock(mutex_id);
+        unsafe {
+            let old = COUNTER;
+            yield_();
+            COUNTER = old + 1;
+        }
+        mutex_unlock(mutex_id);
+    }
+    exit(0)
+}
+
+fn test_mutex(mutex_id: usize) {
+    unsafe {
+        COUNTER = 0;
+    }
+    let handles = [(); THREAD_COUNT].map(|_| thread::spawn(add, mutex_id));
+    for handle in handles {
+        handle.join();
+    }
+    assert_eq!(unsafe { COUNTER }, THREAD_COUNT * PER_THREAD);
+}
+
+//信号量初值为 0 ，子线程 up 之后主线程的 down 才能返回
+fn signal_sem(sem_id: usize) -> ! {
+    unsafe {
+        COUNTER = 1;
+    }
+    semaphore_up(sem_id);
+    exit(0)
+}
+
+fn test_semaphore() {
+    unsafe {
+        COUNTER = 0;
+    }
+    let sem_id = semaphore_create(0) as usize;
+    let handle = thread::spawn(signal_sem, sem_id);
+    semaphore_down(sem_id);
+    assert_eq!(unsafe { COUNTER }, 1);
+    handle.join();
+}
+
+//arg 的低 16 位是互斥锁编号，高位是条件变量编号
+fn set_ready(arg: usize) -> ! {
+    let (mutex_id, condvar_id) = (arg & 0xffff, arg >> 16);
+    mutex_lock(mutex_id);
+    unsafe {
+        READY = true;
+    }
+    condvar_signal(condvar_id);
+    mutex_unlock(mutex_id);
+    exit(0)
+}
+
+fn test_condvar() {
+    let mutex_id = mutex_blocking_create() as usize;
+    let condvar_id = condvar_create() as usize;
+    let handle = thread::spawn(set_ready, mutex_id | condvar_id << 16);
+    mutex_lock(mutex_id);
+    while !unsafe { READY } {
+        condvar_wait(condvar_id, mutex_id);
+    }
+    mutex_unlock(mutex_id);
+    handle.join();
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    test_mutex(mutex_create() as usize);
+    println!("spin mutex OK!");
+    test_mutex(mutex_blocking_create() as usize);
+    println!("blocking mutex OK!");
+    test_semaphore();
+    println!("semaphore OK!");
+    test_condvar();
+    println!("condvar OK!");
+    println!("Test sync OK!");
+    0
+}
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    enable_deadlock_detect, exit, mutex_blocking_create, mutex_lock, mutex_unlock,
+    semaphore_create, semaphore_down, semaphore_up, sleep, thread,
+};
+
+const DEADLOCK: isize = -0xDEAD;
+
+//arg 的低 16 位是第一个资源的编号，高位是第二个资源的编号
+//子线程先持有第二个资源，再申请主线程已经持有的第一个资源
+fn lock_mutexes(arg: usize) -> ! {
+    let (first, second) = (arg & 0xffff, arg >> 16);
+    assert_eq!(mutex_lock(second), 0);
+    assert_eq!(mutex_lock(first), 0);
+    mutex_unlock(first);
+    mutex_unlock(second);
+    exit(0)
+}
+
+fn test_mutex() {
+    let first = mutex_blocking_create() as usize;
+    let second = mutex_blocking_create() as usize;
+    assert_eq!(mutex_lock(first), 0);
+    let handle = thread::spawn(lock_mutexes, first | second << 16);
+    //等子线程持有第二个锁并阻塞在第一个锁上
+    sleep(100);
+    assert_eq!(mutex_lock(second), DEADLOCK);
+    mutex_unlock(first);
+    assert_eq!(handle.join(), 0);
+}
+
+fn down_semaphores(arg: usize) -> ! {
+    let (first, second) = (arg & 0xffff, arg >> 16);
+    assert_eq!(semaphore_down(second), 0);
+    assert_eq!(semaphore_down(first), 0);
+    semaphore_up(first);
+    semaphore_up(second);
+    exit(0)
+}
+
+fn test_semaphore() {
+    let first = semaphore_create(1) as usize;
+    let second = semaphore_create(1) as usize;
+    assert_eq!(semaphore_down(first), 0);
+    let handle = thread::spawn(down_semaphores, first | second << 16);
+    sleep(100);
+    assert_eq!(semaphore_down(second), DEADLOCK);
+    semaphore_up(first);
+    assert_eq!(handle.join(), 0);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(enable_deadlock_detect(true), 0);
+    test_mutex();
+    println!("mutex deadlock detected!");
+    test_semaphore();
+    println!("semaphore deadlock detected!");
+    println!("Test deadlock OK!");
+    0
+}
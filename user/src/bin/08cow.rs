@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+const PAGES: usize = 4;
+const PAGE_SIZE: usize = 4096;
+
+//跨越多个页面的可写数据，fork 之后由父子进程写时复制地共享
+static mut DATA: [u8; PAGES * PAGE_SIZE] = [1; PAGES * PAGE_SIZE];
+
+fn fill(value: u8) {
+    unsafe {
+        for page in 0..PAGES {
+            DATA[page * PAGE_SIZE] = value;
+        }
+    }
+}
+
+fn check(value: u8) {
+    unsafe {
+        for page in 0..PAGES {
+            assert_eq!(DATA[page * PAGE_SIZE], value);
+        }
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    fill(1);
+    let pid = fork();
+    if pid == 0 {
+        //子进程的写入不能被父进程看到
+        check(1);
+        fill(2);
+        check(2);
+        exit(7);
+    }
+    //exit_code 在父进程的用户栈上，由内核写入，同样需要先复制共享的页面
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    check(1);
+    //子进程已经退出，页帧只剩父进程在用，写入时直接恢复写权限
+    fill(3);
+    check(3);
+    println!("Test copy-on-write fork OK!");
+    0
+}
@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+const PAGE_SIZE: usize = 4096;
+//大约是全部物理内存的一半，只有被访问到的页面才会分配页帧
+const PAGES: usize = 1024;
+const STEP: usize = 128;
+
+static mut SPARSE: [u8; PAGES * PAGE_SIZE] = [0; PAGES * PAGE_SIZE];
+
+#[no_mangle]
+fn main() -> i32 {
+    unsafe {
+        for page in (0..PAGES).step_by(STEP) {
+            //.bss 中的页面第一次被访问时是全零的
+            assert_eq!(SPARSE[page * PAGE_SIZE], 0);
+            SPARSE[page * PAGE_SIZE] = page as u8;
+        }
+        for page in (0..PAGES).step_by(STEP) {
+            assert_eq!(SPARSE[page * PAGE_SIZE], page as u8);
+        }
+    }
+    println!("Test lazy allocation OK!");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x1000_0000;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+
+fn write_page(page: usize, value: u8) {
+    let ptr = (START + page * PAGE_SIZE) as *mut u8;
+    unsafe {
+        ptr.write_volatile(value);
+        assert_eq!(ptr.read_volatile(), value);
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let prot = PROT_READ | PROT_WRITE;
+    //起点没有对齐、长度为 0 、没有访问权限、有保留位、只写不读
+    assert_eq!(mmap(START + 1, PAGE_SIZE, prot), -1);
+    assert_eq!(mmap(START, 0, prot), -1);
+    assert_eq!(mmap(START, PAGE_SIZE, 0), -1);
+    assert_eq!(mmap(START, PAGE_SIZE, 0x8 | prot), -1);
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_WRITE), -1);
+    //映射 4 个页面，和它重叠的映射会失败
+    assert_eq!(mmap(START, 4 * PAGE_SIZE, prot), 0);
+    assert_eq!(mmap(START + 3 * PAGE_SIZE, 2 * PAGE_SIZE, prot), -1);
+    for page in 0..4 {
+        write_page(page, page as u8);
+    }
+    //解除中间两个页面的映射，剩下的两段依然可以访问
+    assert_eq!(munmap(START + PAGE_SIZE, 2 * PAGE_SIZE), 0);
+    assert_eq!(munmap(START + PAGE_SIZE, PAGE_SIZE), -1);
+    write_page(0, 10);
+    write_page(3, 13);
+    //空出来的位置可以重新映射
+    assert_eq!(mmap(START + PAGE_SIZE, PAGE_SIZE, prot), 0);
+    write_page(1, 11);
+    //解除的区间中有没有映射的页面时失败
+    assert_eq!(munmap(START, 4 * PAGE_SIZE), -1);
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    assert_eq!(munmap(START + 3 * PAGE_SIZE, PAGE_SIZE), 0);
+    println!("Test mmap/munmap OK!");
+    0
+}
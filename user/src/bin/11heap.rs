@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::sbrk;
+
+const PAGE_SIZE: usize = 4096;
+
+//直接通过 sbrk 扩展和收缩堆
+fn test_sbrk() {
+    let old_brk = sbrk(0);
+    assert!(old_brk > 0);
+    assert_eq!(sbrk(PAGE_SIZE as isize), old_brk);
+    let ptr = old_brk as *mut u8;
+    unsafe {
+        ptr.write_volatile(0x5a);
+        assert_eq!(ptr.read_volatile(), 0x5a);
+    }
+    assert_eq!(sbrk(-(PAGE_SIZE as isize)), old_brk + PAGE_SIZE as isize);
+    assert_eq!(sbrk(0), old_brk);
+    //堆顶不能低于堆底
+    assert_eq!(sbrk(isize::MIN), -1);
+}
+
+fn test_collections() {
+    let mut v: Vec<usize> = Vec::new();
+    for i in 0..10000 {
+        v.push(i);
+    }
+    assert_eq!(v.iter().sum::<usize>(), 10000 * 9999 / 2);
+    let mut s = String::new();
+    for i in 0..100 {
+        s.push_str(if i % 2 == 0 { "even" } else { "odd" });
+    }
+    assert_eq!(s.len(), 50 * 4 + 50 * 3);
+    let mut map = BTreeMap::new();
+    for i in 0..1000 {
+        map.insert(i, i * i);
+    }
+    assert_eq!(map.get(&30), Some(&900));
+    map.retain(|k, _| k % 2 == 0);
+    assert_eq!(map.len(), 500);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    //全局分配器还没有使用堆之前先单独测试 sbrk
+    test_sbrk();
+    test_collections();
+    println!("Test heap OK!");
+    0
+}
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, waitpid};
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x1000_0000;
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+
+fn page(index: usize) -> *mut u8 {
+    (START + index * PAGE_SIZE) as *mut u8
+}
+
+//在子进程中写入 index 号页面，返回子进程的退出码
+fn write_in_child(index: usize) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            page(index).write_volatile(0xff);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let prot = PROT_READ | PROT_WRITE;
+    assert_eq!(mmap(START, 3 * PAGE_SIZE, prot), 0);
+    for index in 0..3 {
+        unsafe {
+            page(index).write_volatile(index as u8);
+        }
+    }
+    //参数不合法或者区间中有没有映射的页面
+    assert_eq!(mprotect(START + 1, PAGE_SIZE, PROT_READ), -1);
+    assert_eq!(mprotect(START, PAGE_SIZE, 0), -1);
+    assert_eq!(mprotect(START, 4 * PAGE_SIZE, PROT_READ), -1);
+    //中间的页面变为只读，内容不变，写入它的进程会被杀死，两边的页面不受影响
+    assert_eq!(mprotect(START + PAGE_SIZE, PAGE_SIZE, PROT_READ), 0);
+    assert_eq!(unsafe { page(1).read_volatile() }, 1);
+    assert_eq!(write_in_child(1), -2);
+    assert_eq!(write_in_child(0), 0);
+    assert_eq!(write_in_child(2), 0);
+    //恢复写权限之后可以再次写入
+    assert_eq!(mprotect(START, 3 * PAGE_SIZE, prot), 0);
+    unsafe {
+        page(1).write_volatile(11);
+        assert_eq!(page(1).read_volatile(), 11);
+    }
+    println!("Test mprotect OK!");
+    0
+}
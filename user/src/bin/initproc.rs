@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, fork, wait};
+
+#[no_mangle]
+fn main() -> i32 {
+    let shell_pid = fork();
+    if shell_pid == 0 {
+        //子进程运行 shell
+        exec("user_shell\0");
+        println!("[initproc] Failed to exec user_shell");
+        return -4;
+    }
+    //回收所有子进程，包括被过继过来的孤儿进程；没有子进程之后再退出
+    let mut shell_exit_code: i32 = 0;
+    loop {
+        let mut exit_code: i32 = 0;
+        let pid = wait(&mut exit_code);
+        if pid == -1 {
+            break;
+        }
+        if pid == shell_pid {
+            shell_exit_code = exit_code;
+        } else {
+            println!(
+                "[initproc] Released a zombie process, pid={}, exit_code={}",
+                pid, exit_code
+            );
+        }
+    }
+    shell_exit_code
+}
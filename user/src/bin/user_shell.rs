@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, fork, read, waitpid};
+
+const STDIN: usize = 0;
+
+//一行命令的最大长度，最后一个字节留给 \0
+const LINE_MAX: usize = 128;
+
+fn help() {
+    println!("Builtin commands:");
+    println!("    help          show this message");
+    println!("    exit [code]   exit the shell with an optional exit code");
+    println!("Any other input is run as the application with that name.");
+}
+
+//解析 exit 后面的退出码，没有给出时为 0
+fn parse_exit_code(arg: &str) -> Option<i32> {
+    if arg.is_empty() {
+        return Some(0);
+    }
+    arg.parse::<i32>().ok()
+}
+
+//fork 一个子进程执行应用并等待它退出
+fn run(name: &str) {
+    if name.len() >= LINE_MAX {
+        println!("Application name too long!");
+        return;
+    }
+    //exec 需要以 \0 结尾的应用名
+    let mut path = [0u8; LINE_MAX];
+    path[..name.len()].copy_from_slice(name.as_bytes());
+    let path = core::str::from_utf8(&path[..name.len() + 1]).unwrap();
+    let pid = fork();
+    if pid == 0 {
+        // child process
+        if exec(path) == -1 {
+            println!("Error when executing {}!", name);
+            user_lib::exit(-4);
+        }
+        unreachable!();
+    } else {
+        let mut exit_code: i32 = 0;
+        let exit_pid = waitpid(pid as usize, &mut exit_code);
+        assert_eq!(pid, exit_pid);
+        println!("Shell: Process {} exited with code {}", pid, exit_code);
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("Rust user shell");
+    //标准输入默认处于规范模式：回显、退格都由内核的行规程处理，read 每次返回一整行
+    let mut line = [0u8; LINE_MAX];
+    loop {
+        print!(">> ");
+        let len = read(STDIN, &mut line);
+        if len <= 0 {
+            continue;
+        }
+        let input = core::str::from_utf8(&line[..len as usize]).unwrap_or("").trim();
+        let (cmd, arg) = match input.find(' ') {
+            Some(idx) => (&input[..idx], input[idx + 1..].trim()),
+            None => (input, ""),
+        };
+        match cmd {
+            "" => {}
+            "help" => help(),
+            "exit" => match parse_exit_code(arg) {
+                Some(exit_code) => return exit_code,
+                None => println!("exit: invalid exit code {}", arg),
+            },
+            _ => run(cmd),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 //print宏
-use super::write; //super指向当前模块的父模块lib
+use super::{read, write}; //super指向当前模块的父模块lib
 use core::fmt::{self, Write};
 
+const STDIN: usize = 0;
 const STDOUT: usize = 1;
 
 struct Stdout;
@@ -20,6 +21,13 @@ pub fn print(args: fmt::Arguments) {
     Stdout.write_fmt(args).unwrap();
 }
 
+//从标准输入读取一个字符
+pub fn getchar() -> u8 {
+    let mut c = [0u8; 1];
+    read(STDIN, &mut c);
+    c[0]
+}
+
 #[macro_export]
 macro_rules! print {
     ($fmt: literal $(, $($arg: tt)+)?) => {
@@ -0,0 +1,39 @@
+use crate::sbrk;
+use buddy_system_allocator::LockedHeap;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{null_mut, NonNull};
+
+//每次至少通过 sbrk 向内核申请的堆空间大小
+const HEAP_GROW_SIZE: usize = 0x10000;
+
+//用户程序的堆：一开始是空的，分配失败时通过 sbrk 扩展堆，把新的空间交给伙伴分配器后重试
+struct UserHeap(LockedHeap);
+
+#[global_allocator]
+static HEAP_ALLOCATOR: UserHeap = UserHeap(LockedHeap::empty());
+
+unsafe impl GlobalAlloc for UserHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.0.lock();
+        loop {
+            if let Ok(ptr) = heap.alloc(layout) {
+                return ptr.as_ptr();
+            }
+            //伙伴分配器分配的块按自身大小对齐，新的空间至少要是块大小的两倍才一定能放下这个块
+            let size = (layout.size().max(layout.align()).next_power_of_two() * 2).max(HEAP_GROW_SIZE);
+            let old_brk = sbrk(size as isize);
+            if old_brk < 0 {
+                return null_mut();
+            }
+            heap.add_to_heap(old_brk as usize, old_brk as usize + size);
+        }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.lock().dealloc(NonNull::new_unchecked(ptr), layout);
+    }
+}
+
+#[alloc_error_handler]
+fn handle_alloc_error(layout: Layout) -> ! {
+    panic!("Heap allocation error, layout = {:?}", layout);
+}
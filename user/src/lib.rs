@@ -1,13 +1,18 @@
 #![no_std]
 #![feature(panic_info_message)]
 #![feature(linkage)] //支持下面的链接操作
+#![feature(alloc_error_handler)]
 
-use syscall::{sys_exit, sys_write, sys_yield, sys_get_time};
+extern crate alloc;
+
+use syscall::*;
 
 #[macro_use] //外部的crate，想要使用console这个crate提供的宏时
 pub mod console;
 mod syscall;
 mod lang_items;
+mod heap_allocator;
+pub mod thread;
 
 #[no_mangle]
 #[link_section = ".text.entry"] //将_start编译后的汇编代码放到名为.text.entry的代码段中。方便后续链接的时候调整它的位置使得它能够作为用户库的入口。
@@ -38,6 +43,28 @@ fn clear_bss() {
     })
 }
 
+//TTY 模式标志位，与内核 tty 模块中的定义一致
+pub const TTY_ICANON: usize = 0o2; //规范模式：按行读取，支持退格
+pub const TTY_ECHO: usize = 0o10; //回显输入的字符
+
+const STDIN: usize = 0;
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+
+//获取标准输入当前的 TTY 模式
+pub fn tty_get_mode() -> usize {
+    sys_ioctl(STDIN, TCGETS, 0) as usize
+}
+
+//设置标准输入的 TTY 模式，例如 tty_set_mode(0) 进入不回显的原始模式
+pub fn tty_set_mode(flags: usize) -> isize {
+    sys_ioctl(STDIN, TCSETS, flags)
+}
+
+pub fn read(fd: usize, buf: &mut [u8]) -> isize {
+    sys_read(fd, buf)
+}
+
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
 }
@@ -50,6 +77,206 @@ pub fn yield_() -> isize {
     sys_yield()
 }
 
+//设置当前进程的 stride 调度优先级（2 到 65536 之间），返回设置的优先级或 -1
+pub fn set_priority(prio: isize) -> isize {
+    sys_set_priority(prio)
+}
+
+//睡眠时长，usec 必须小于 1000000
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+//阻塞当前进程 ms 毫秒，期间不占用 CPU
+pub fn sleep(ms: usize) -> isize {
+    sys_sleep(ms)
+}
+
+pub fn nanosleep(req: &TimeVal) -> isize {
+    sys_nanosleep(req)
+}
+
+//设置当前进程的时间片长度（ms，大于 0），返回设置的长度或 -1
+pub fn set_time_slice(ms: isize) -> isize {
+    sys_set_time_slice(ms)
+}
+
+//与内核中的 MAX_SYSCALL_NUM 一致，覆盖所有的系统调用号
+pub const MAX_SYSCALL_NUM: usize = 1040;
+
+//与内核中的 TaskStatus 取值一致
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(usize)]
+pub enum TaskStatus {
+    Ready,
+    Running,
+    Blocked,
+    Zombie,
+}
+
+//与内核中 TaskInfo 的布局一致
+#[repr(C)]
+pub struct TaskInfo {
+    pub status: TaskStatus,
+    pub syscall_times: [u32; MAX_SYSCALL_NUM], //每个系统调用被调用的次数，下标为系统调用号
+    pub time: usize, //从第一次被调度到现在经过的时间（ms）
+    pub user_time: usize, //在用户态累计运行的时间（ms）
+    pub kernel_time: usize, //在内核态累计运行的时间（ms）
+}
+
+impl TaskInfo {
+    pub fn new() -> Self {
+        Self {
+            status: TaskStatus::Ready,
+            syscall_times: [0; MAX_SYSCALL_NUM],
+            time: 0,
+            user_time: 0,
+            kernel_time: 0,
+        }
+    }
+}
+
+//查询 pid 为 id 的任务的统计信息，找不到任务时返回 -1
+pub fn task_info(id: usize, info: &mut TaskInfo) -> isize {
+    sys_task_info(id, info)
+}
+
 pub fn get_time() -> isize {
     sys_get_time()
-}
\ No newline at end of file
+}
+
+pub fn getpid() -> isize {
+    sys_getpid()
+}
+
+pub fn fork() -> isize {
+    sys_fork()
+}
+
+pub fn exec(path: &str) -> isize {
+    sys_exec(path)
+}
+
+//把堆顶移动 size 字节（可以为负），返回原来的堆顶，失败时返回 -1
+//堆由用户库的全局分配器管理，应用一般直接使用 alloc 中的 Vec 、String 等集合
+pub fn sbrk(size: isize) -> isize {
+    sys_sbrk(size)
+}
+
+//在 [start, start + len) 映射一段匿名内存，prot 的低 3 位依次表示可读、可写、可执行
+//start 必须按页对齐，参数不合法或者和已有的映射重叠时返回 -1
+pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
+    sys_mmap(start, len, prot)
+}
+
+//把 [start, start + len) 的访问权限改为 prot ，可以只修改一段映射的一部分；不能用于 sbrk 管理的堆
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+
+//解除 [start, start + len) 的映射，可以只解除一段映射的一部分；不能用于 sbrk 管理的堆
+pub fn munmap(start: usize, len: usize) -> isize {
+    sys_munmap(start, len)
+}
+
+//等待任意一个子进程退出，子进程还在运行时让出 CPU 并重试
+pub fn wait(exit_code: &mut i32) -> isize {
+    loop {
+        match sys_waitpid(-1, exit_code as *mut _) {
+            -2 => {
+                yield_();
+            }
+            // -1 or a real pid
+            exit_pid => return exit_pid,
+        }
+    }
+}
+
+//等待指定的子进程退出
+pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
+    loop {
+        match sys_waitpid(pid as isize, exit_code as *mut _) {
+            -2 => {
+                yield_();
+            }
+            // -1 or a real pid
+            exit_pid => return exit_pid,
+        }
+    }
+}
+
+//在当前进程中创建一个从 entry 开始执行的线程，arg 作为它的第一个参数，返回新线程的 tid
+//线程函数不能返回，结束时需要调用 exit
+pub fn thread_create(entry: usize, arg: usize) -> isize {
+    sys_thread_create(entry, arg)
+}
+
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+//等待同一进程中的线程退出并返回它的退出码，线程不存在或者是当前线程时返回 -1
+pub fn waittid(tid: usize) -> isize {
+    loop {
+        match sys_waittid(tid) {
+            -2 => {
+                yield_();
+            }
+            exit_code => return exit_code,
+        }
+    }
+}
+
+//创建一个自旋锁，返回它的编号
+pub fn mutex_create() -> isize {
+    sys_mutex_create(false)
+}
+
+//创建一个阻塞锁，返回它的编号
+pub fn mutex_blocking_create() -> isize {
+    sys_mutex_create(true)
+}
+
+pub fn mutex_lock(mutex_id: usize) -> isize {
+    sys_mutex_lock(mutex_id)
+}
+
+//互斥锁不存在或者没有被锁上时返回 -1
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
+}
+
+//创建一个初始资源数为 res_count 的信号量，返回它的编号
+pub fn semaphore_create(res_count: usize) -> isize {
+    sys_semaphore_create(res_count)
+}
+
+pub fn semaphore_up(sem_id: usize) -> isize {
+    sys_semaphore_up(sem_id)
+}
+
+pub fn semaphore_down(sem_id: usize) -> isize {
+    sys_semaphore_down(sem_id)
+}
+
+//启用死锁检测后，会导致死锁的 mutex_lock 和 semaphore_down 直接返回 -0xDEAD 而不是阻塞
+pub fn enable_deadlock_detect(enabled: bool) -> isize {
+    sys_enable_deadlock_detect(enabled as usize)
+}
+
+//创建一个条件变量，返回它的编号
+pub fn condvar_create() -> isize {
+    sys_condvar_create()
+}
+
+pub fn condvar_signal(condvar_id: usize) -> isize {
+    sys_condvar_signal(condvar_id)
+}
+
+//释放互斥锁并等待条件变量，返回前会重新获取互斥锁
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    sys_condvar_wait(condvar_id, mutex_id)
+}
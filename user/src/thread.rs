@@ -0,0 +1,27 @@
+//! 仿照 std::thread 的线程接口
+//!
+//! 和 std 不同，这里不接受闭包：线程函数以一个 usize 为参数并且不能返回，结束时调用 exit 。
+
+use super::{thread_create, waittid};
+
+//已创建线程的句柄，通过 join 等待它退出
+pub struct JoinHandle {
+    tid: usize,
+}
+
+impl JoinHandle {
+    pub fn tid(&self) -> usize {
+        self.tid
+    }
+    //等待线程退出，返回它的退出码
+    pub fn join(self) -> i32 {
+        waittid(self.tid) as i32
+    }
+}
+
+//创建一个执行 f(arg) 的线程
+pub fn spawn(f: fn(usize) -> !, arg: usize) -> JoinHandle {
+    let tid = thread_create(f as usize, arg);
+    assert!(tid >= 0, "failed to create thread");
+    JoinHandle { tid: tid as usize }
+}